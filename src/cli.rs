@@ -1,31 +1,114 @@
 pub mod commands;
 pub mod models;
+pub mod tui;
 
-use clap::Parser as _;
-use commands::{analyze, delete_channel, join_channel};
+use clap::{error::ErrorKind, CommandFactory as _, Parser as _};
+use commands::{
+    analyze, archive_chats, delete_channel, join_channel, mute_chats, organize, record_analysis,
+    session_list, session_logout, session_status, session_terminate,
+};
 use grammers_client::Client;
-use models::{Cli, Commands};
+use models::{Cli, ClientOverrides, Commands, ConnectedCommand};
 
+use crate::{
+    configs::{read_secret_file, Config, Overrides, OverridesError, Profile},
+    errors::Error,
+};
+
+/// Conflicts of `--all-profiles` with the global flags are checked by clap only if the flags
+/// follow the subcommand, so the ones before it are checked here
 pub fn parse() -> Cli {
-    Cli::parse()
+    let cli = Cli::parse();
+
+    if let Commands::Analyze(ref analyze) = cli.command {
+        let overrides = &cli.client_overrides;
+        let account_flag = [
+            ("--profile", cli.profile.is_some()),
+            ("--phone-number", overrides.phone_number.is_some()),
+            ("--password-file", overrides.password_file.is_some()),
+            ("--session-file-path", overrides.session_file_path.is_some()),
+        ]
+        .into_iter()
+        .find_map(|(flag, is_set)| is_set.then_some(flag));
+
+        if let (true, Some(flag)) = (analyze.all_profiles, account_flag) {
+            Cli::command()
+                .error(
+                    ErrorKind::ArgumentConflict,
+                    format!("the argument '--all-profiles' cannot be used with '{flag}'"),
+                )
+                .exit();
+        }
+    }
+
+    cli
+}
+
+pub fn overrides(args: ClientOverrides) -> Result<Overrides, OverridesError> {
+    let password = match args.password_file {
+        Some(path) => Some(
+            read_secret_file(&path)
+                .map_err(|source| OverridesError::SecretFile { path, source })?,
+        ),
+        None => None,
+    };
+
+    Ok(Overrides {
+        api_id: args.api_id,
+        api_hash: args.api_hash.map(Into::into),
+        password,
+        phone_number: args.phone_number.map(Into::into),
+        session_file_path: args.session_file_path,
+        session_passphrase: None,
+    })
 }
 
-pub async fn run(client: &Client, command: Commands) {
+pub async fn run(
+    client: &Client,
+    config: &Config,
+    profile: &Profile<'_>,
+    command: ConnectedCommand,
+) -> Result<(), Error> {
     match command {
-        Commands::Analyze(config) => {
-            analyze(config, client)
-                .await
-                .expect("Error while analyze chats");
-        }
-        Commands::Join(config) => {
-            join_channel(config, client)
-                .await
-                .expect("Error while join channel/supergroup");
-        }
-        Commands::Delete(config) => {
-            delete_channel(config, client)
-                .await
-                .expect("Error while join channel/supergroup");
+        ConnectedCommand::Analyze(analyze_config) => match analyze_config.record {
+            Some(ref fixture_path) => {
+                record_analysis(
+                    &analyze_config,
+                    client,
+                    profile.name,
+                    fixture_path,
+                    &config.ignore_file_path(),
+                )
+                .await?;
+            }
+            None => {
+                analyze(
+                    &analyze_config,
+                    client,
+                    Some(profile.name),
+                    &config.ignore_file_path(),
+                )
+                .await?;
+            }
+        },
+        ConnectedCommand::Join(config) => join_channel(config, client).await?,
+        ConnectedCommand::Delete(config) => delete_channel(config, client).await?,
+        ConnectedCommand::Archive(config) => archive_chats(config, client, profile.name).await?,
+        ConnectedCommand::Mute(config) => mute_chats(config, client, profile.name).await?,
+        ConnectedCommand::Organize(organize_config) => {
+            organize(
+                &organize_config,
+                client,
+                profile.name,
+                &config.ignore_file_path(),
+            )
+            .await?;
         }
+        ConnectedCommand::SessionStatus => session_status(client, &profile.client).await?,
+        ConnectedCommand::SessionLogout => session_logout(client, &profile.client).await?,
+        ConnectedCommand::SessionList => session_list(client).await?,
+        ConnectedCommand::SessionTerminate(config) => session_terminate(config, client).await?,
     };
+
+    Ok(())
 }
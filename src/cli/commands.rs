@@ -1,15 +1,183 @@
-use std::process;
+use std::{
+    cell::RefCell,
+    fs,
+    future::Future,
+    path::{Path, PathBuf},
+};
+
+use chrono::DateTime;
 
-use super::models::{Analyze, Delete, Join};
-use crate::client::{
-    analyze::{self, Chat},
-    delete, invite_link, join,
+use super::{
+    models::{
+        Analyze, ChatKind, ChatsTarget, Delete, IgnoreCommands, IgnoreEntry, Join, Organize,
+        Terminate,
+    },
+    tui::{self, Action, Item},
+};
+use crate::{
+    client::{
+        analyze::{self, ArchiveFilter, Chat, ChatFilter, MembersHistory, Report, Rules},
+        api::{Export, Recorder, Replay, TelegramApi},
+        auth, folders, session,
+        session_file::{self, SessionError},
+    },
+    configs::{
+        self, check_api_hash, check_api_id, check_phone_number, check_session_file_path,
+        default_session_file_path, ClientConfig, IgnoreError, IgnoreList, IgnoreMatcher, Overrides,
+        Template, DEFAULT_PROFILE_NAME,
+    },
+    errors::Error,
 };
 use grammers_client::{client::bots::InvocationError, Client};
 use grammers_mtsender::RpcError;
+use grammers_session::{PackedChat, PackedType};
+use tokio::task;
 use tracing::{debug, error};
 
-pub async fn analyze(config: Analyze, client: &Client) -> Result<(), InvocationError> {
+async fn get_invite_link(client: &impl TelegramApi, chat: &Chat) -> Option<Box<str>> {
+    match client.export_invite_link(chat.packed()?).await {
+        Ok(link) => link,
+        Err(err) => {
+            debug!(%err, "Error while get invite link");
+            None
+        }
+    }
+}
+
+async fn review_chats(
+    client: &impl TelegramApi,
+    chats: Vec<Chat>,
+    mut ignore_list: IgnoreList,
+    ignore_file_path: &Path,
+) -> Result<(), Error> {
+    if chats.is_empty() {
+        println!("No chats found");
+        return Ok(());
+    }
+
+    println!("Get invite links of {count} chats", count = chats.len());
+
+    let mut items = Vec::with_capacity(chats.len());
+    for chat in chats {
+        items.push(Item {
+            link: get_invite_link(client, &chat).await,
+            chat,
+            action: None,
+        });
+    }
+
+    // The terminal UI blocks the thread while it waits for keys
+    if !task::block_in_place(|| tui::review(&mut items)).map_err(Error::Terminal)? {
+        println!("No changes are made");
+        return Ok(());
+    }
+
+    let mut ignored_count = 0;
+    for Item { chat, action, .. } in items {
+        let Some(action) = action else {
+            continue;
+        };
+
+        let (title, id) = (chat.title().unwrap_or("unknown"), chat.id());
+        let result = match (action, chat.packed()) {
+            (Action::Ignore, _) => {
+                ignore_list.ids.insert(id);
+                ignored_count += 1;
+                continue;
+            }
+            // Marking such chats isn't available, but the action isn't dropped silently anyway
+            (_, None) => {
+                println!("Failed: {action} {title} ({id}) (chat isn't available)");
+                continue;
+            }
+            (Action::Join, Some(packed)) => client.join_channel(packed).await,
+            (Action::Leave, Some(packed)) => client.leave_chat(packed).await,
+            (Action::Delete, Some(packed)) => client.delete_channel(packed).await,
+            (Action::Archive, Some(packed)) => client.archive_chat(packed).await,
+        };
+
+        match result {
+            Ok(()) => println!("Done: {action} {title} ({id})"),
+            Err(err) => println!("Failed: {action} {title} ({id}): {err}"),
+        }
+    }
+
+    if ignored_count > 0 {
+        ignore_list.save(ignore_file_path)?;
+
+        println!(
+            "{ignored_count} chat(s) are added to the ignore list `{path}`",
+            path = ignore_file_path.display(),
+        );
+    }
+
+    Ok(())
+}
+
+impl From<ChatKind> for PackedType {
+    fn from(kind: ChatKind) -> Self {
+        match kind {
+            ChatKind::Group => PackedType::Chat,
+            ChatKind::Megagroup => PackedType::Megagroup,
+            ChatKind::Broadcast => PackedType::Broadcast,
+            ChatKind::Gigagroup => PackedType::Gigagroup,
+        }
+    }
+}
+
+async fn chat_filter(config: &Analyze, client: &impl TelegramApi) -> Result<ChatFilter, Error> {
+    let folder = match config.folder {
+        Some(ref name) => match folders::find_folder(client, name).await? {
+            Ok(folder) => Some(folder),
+            Err(available) => {
+                return Err(Error::FolderNotFound {
+                    name: name.as_str().into(),
+                    available,
+                })
+            }
+        },
+        None => None,
+    };
+
+    Ok(ChatFilter {
+        kinds: config.kinds.iter().copied().map(Into::into).collect(),
+        title: config.title_regex.clone(),
+        username: config
+            .username
+            .as_deref()
+            .map(|username| username.trim_start_matches('@').into()),
+        min_members: config.min_members,
+        max_members: config.max_members,
+        folder,
+        archive: if config.archived_only {
+            ArchiveFilter::Only
+        } else if config.exclude_archived {
+            ArchiveFilter::Exclude
+        } else {
+            ArchiveFilter::Any
+        },
+    })
+}
+
+async fn find_chats(
+    config: &Analyze,
+    client: &impl TelegramApi,
+    ignored: &IgnoreMatcher,
+) -> Result<Vec<Chat>, Error> {
+    let filter = chat_filter(config, client).await?;
+    let members_history = config
+        .members_history
+        .as_deref()
+        .map(MembersHistory::load)
+        .transpose()?;
+    let rules = Rules {
+        activity_months: config.activity_months.map(usize::from),
+        own_message_days: config.own_message_days.map(i64::from),
+        min_unread: config.min_unread,
+        members: config.members,
+        members_history: members_history.map(RefCell::new),
+    };
+
     let mut chats: Vec<Chat> = vec![];
 
     if config.joined {
@@ -17,33 +185,24 @@ pub async fn analyze(config: Analyze, client: &Client) -> Result<(), InvocationE
 
         chats = chats
             .into_iter()
-            .chain(analyze::get_chats(client).await.unwrap())
+            .chain(analyze::get_chats(client, ignored, &filter, &rules).await?)
             .collect();
+
+        if let (Some(path), Some(history)) = (&config.members_history, &rules.members_history) {
+            history.borrow().save(path)?;
+
+            println!("Member counts are saved to `{path}`", path = path.display());
+        }
     }
     if config.left {
         println!("Analyze the chats that you're left. It may take a few minutes.");
 
-        let takeout_id = match analyze::init_takeout_session(client).await {
-            Ok(takeout_id) => takeout_id,
-            Err(err) => {
-                match err {
-                    InvocationError::Rpc(RpcError {
-                        code: code @ 420,
-                        value,
-                        ..
-                    }) => {
-                        error!(?value, code, "Sorry, for security reasons, you will be able to begin downloading your data in %d seconds. We have notified all your devices about the export request to make sure it's authorized and to give you time to react if it's not.");
-                    }
-                    _ => {
-                        error!(%err, "Error while get group messages");
-                    }
-                };
-
-                process::exit(1);
-            }
-        };
+        let takeout_id = client
+            .init_takeout_session()
+            .await
+            .map_err(Error::Takeout)?;
 
-        let success = match analyze::get_left_chats(client, takeout_id).await {
+        let success = match analyze::get_left_chats(client, takeout_id, ignored, &filter).await {
             Ok(left_chats) => {
                 chats = chats.into_iter().chain(left_chats).collect();
                 true
@@ -53,20 +212,35 @@ pub async fn analyze(config: Analyze, client: &Client) -> Result<(), InvocationE
                 false
             }
         };
-        analyze::finish_takeout_session(client, takeout_id, success).await?;
+        client.finish_takeout_session(takeout_id, success).await?;
+    }
+
+    Ok(chats)
+}
+
+/// `profile` is the one of the analyzed account, it's `None` for offline sources
+pub async fn analyze(
+    config: &Analyze,
+    client: &impl TelegramApi,
+    profile: Option<&str>,
+    ignore_file_path: &Path,
+) -> Result<(), Error> {
+    let ignore_list = IgnoreList::load(ignore_file_path)?;
+    let chats = find_chats(config, client, &ignore_list.matcher()?).await?;
+
+    if let Some(ref path) = config.save_report {
+        Report::new(profile, &chats).save(path)?;
+
+        println!("Report is saved to `{path}`", path = path.display());
+    }
+
+    if config.tui {
+        return review_chats(client, chats, ignore_list, ignore_file_path).await;
     }
 
     for chat in chats {
-        if let Some(chat_invite) =
-            match invite_link::get_chat_invite(client, chat.id(), chat.access_hash()).await {
-                Ok(val) => val,
-                Err(err) => {
-                    debug!(%err, "Error while get invite link");
-                    None
-                }
-            }
-        {
-            println!("{chat} ({link})", link = chat_invite.link);
+        if let Some(link) = get_invite_link(client, &chat).await {
+            println!("{chat} ({link})");
         } else {
             println!("{chat}");
         }
@@ -75,8 +249,115 @@ pub async fn analyze(config: Analyze, client: &Client) -> Result<(), InvocationE
     Ok(())
 }
 
-pub async fn join_channel(config: Join, client: &Client) -> Result<(), InvocationError> {
-    match join::join_channel(client, config.id, config.access_hash).await {
+/// Chats found for a profile with their invite links, which can only be got by its client
+pub struct ProfileChats {
+    pub profile: Box<str>,
+    pub chats: Vec<(Chat, Option<Box<str>>)>,
+}
+
+pub async fn analyze_profile(
+    config: &Analyze,
+    client: &impl TelegramApi,
+    profile: &str,
+    ignore_file_path: &Path,
+) -> Result<ProfileChats, Error> {
+    let ignored = IgnoreList::load(ignore_file_path)?.matcher()?;
+    let found = find_chats(config, client, &ignored).await?;
+
+    let mut chats = Vec::with_capacity(found.len());
+    for chat in found {
+        let link = get_invite_link(client, &chat).await;
+        chats.push((chat, link));
+    }
+
+    Ok(ProfileChats {
+        profile: profile.into(),
+        chats,
+    })
+}
+
+/// One list of the chats of all profiles, each prefixed with its profile
+pub fn show_all_profiles(config: &Analyze, profiles: &[ProfileChats]) -> Result<(), Error> {
+    if let Some(ref path) = config.save_report {
+        Report::merged(
+            profiles
+                .iter()
+                .map(|found| (&*found.profile, found.chats.iter().map(|(chat, _)| chat))),
+        )
+        .save(path)?;
+
+        println!("Report is saved to `{path}`", path = path.display());
+    }
+
+    for ProfileChats { profile, chats } in profiles {
+        for (chat, link) in chats {
+            if let Some(link) = link {
+                println!("[{profile}] {chat} ({link})");
+            } else {
+                println!("[{profile}] {chat}");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Type of the channel doesn't matter for the requests by ID
+const fn channel(id: i64, access_hash: Option<i64>) -> PackedChat {
+    PackedChat {
+        ty: PackedType::Megagroup,
+        id,
+        access_hash,
+    }
+}
+
+/// Analysis with the responses recorded to the fixture. It's saved even if the analysis fails,
+/// so the failure can be reproduced too.
+pub async fn record_analysis(
+    config: &Analyze,
+    client: &impl TelegramApi,
+    profile: &str,
+    fixture_path: &Path,
+    ignore_file_path: &Path,
+) -> Result<(), Error> {
+    let recorder = Recorder::new(client);
+    let result = analyze(config, &recorder, Some(profile), ignore_file_path).await;
+    recorder.save(fixture_path)?;
+
+    println!(
+        "Responses are recorded to `{path}`",
+        path = fixture_path.display(),
+    );
+
+    result
+}
+
+/// Analysis of the recorded responses, it doesn't need a connected client
+pub async fn replay_analysis(
+    config: &Analyze,
+    fixture_path: &Path,
+    ignore_file_path: &Path,
+) -> Result<(), Error> {
+    let replay = Replay::load(fixture_path)?;
+
+    analyze(config, &replay, None, ignore_file_path).await
+}
+
+pub async fn export_analysis(
+    config: &Analyze,
+    export_path: &Path,
+    ignore_file_path: &Path,
+) -> Result<(), Error> {
+    let export = Export::load(export_path)?;
+
+    analyze(config, &export, None, ignore_file_path).await
+}
+
+pub async fn join_channel(config: Join, client: &impl TelegramApi) -> Result<(), InvocationError> {
+    match client
+        .join_channel(channel(config.id, config.access_hash))
+        .await
+    {
         Ok(()) => {
             println!("You have joined the channel/supergroup");
             Ok(())
@@ -93,8 +374,14 @@ pub async fn join_channel(config: Join, client: &Client) -> Result<(), Invocatio
     }
 }
 
-pub async fn delete_channel(config: Delete, client: &Client) -> Result<(), InvocationError> {
-    match delete::delete_channel(client, config.id, config.access_hash).await {
+pub async fn delete_channel(
+    config: Delete,
+    client: &impl TelegramApi,
+) -> Result<(), InvocationError> {
+    match client
+        .delete_channel(channel(config.id, config.access_hash))
+        .await
+    {
         Ok(()) => {
             println!("You have deleted the channel/supergroup");
             Ok(())
@@ -110,3 +397,683 @@ pub async fn delete_channel(config: Delete, client: &Client) -> Result<(), Invoc
         Err(err) => Err(err),
     }
 }
+
+/// Run the action for the chat by ID, the channel is requested if the access hash is specified.
+async fn for_chat<'a, A, F, Fut>(
+    client: &'a A,
+    id: i64,
+    access_hash: Option<i64>,
+    action: F,
+    done: &str,
+) -> Result<(), Error>
+where
+    A: TelegramApi,
+    F: Fn(&'a A, PackedChat) -> Fut,
+    Fut: Future<Output = Result<(), InvocationError>>,
+{
+    let chat = if access_hash.is_some() {
+        channel(id, access_hash)
+    } else {
+        PackedChat {
+            ty: PackedType::Chat,
+            id,
+            access_hash: None,
+        }
+    };
+
+    match action(client, chat).await {
+        Ok(()) => println!("{done} the chat"),
+        Err(InvocationError::Rpc(RpcError {
+            code: _code @ 400, ..
+        })) => {
+            println!(
+                "Chat invalid. Probably specified incorrect chat/channel ID or access hash of the channel is missing."
+            );
+        }
+        Err(err) => return Err(err.into()),
+    }
+
+    Ok(())
+}
+
+/// Run the action for the chat by ID or for the chats from the report you're still a member of.
+/// Errors of chats from the report are logged, so the rest of them are processed anyway.
+async fn for_target_chats<'a, A, F, Fut>(
+    config: ChatsTarget,
+    client: &'a A,
+    profile: &str,
+    action: F,
+    done: &str,
+) -> Result<(), Error>
+where
+    A: TelegramApi,
+    F: Fn(&'a A, PackedChat) -> Fut,
+    Fut: Future<Output = Result<(), InvocationError>>,
+{
+    let path = match (config.id, config.report) {
+        (_, Some(path)) => path,
+        (Some(id), None) => return for_chat(client, id, config.access_hash, action, done).await,
+        (None, None) => return Err(Error::ChatNotSpecified),
+    };
+
+    let report = Report::load(&path, profile)?;
+
+    let mut processed = 0;
+    let mut skipped = 0;
+    for chat in &report.chats {
+        let Some(packed) = chat.packed().filter(|_| !chat.left) else {
+            skipped += 1;
+            continue;
+        };
+
+        match action(client, packed).await {
+            Ok(()) => {
+                processed += 1;
+
+                println!(
+                    "{done} {title} ({id})",
+                    title = chat.title.as_deref().unwrap_or("untitled chat"),
+                    id = chat.id,
+                );
+            }
+            Err(err) => error!(%err, id = chat.id, "Error while process the chat"),
+        }
+    }
+
+    println!(
+        "{done} {processed} of {total} chat(s) from the report, {skipped} chat(s) you aren't a member of are skipped",
+        total = report.chats.len(),
+    );
+
+    Ok(())
+}
+
+pub async fn archive_chats<A: TelegramApi>(
+    config: ChatsTarget,
+    client: &A,
+    profile: &str,
+) -> Result<(), Error> {
+    for_target_chats(config, client, profile, A::archive_chat, "Archived").await
+}
+
+pub async fn mute_chats<A: TelegramApi>(
+    config: ChatsTarget,
+    client: &A,
+    profile: &str,
+) -> Result<(), Error> {
+    for_target_chats(config, client, profile, A::mute_chat, "Muted").await
+}
+
+/// Chats are taken from the report or found by the analysis of joined chats
+pub async fn organize(
+    config: &Organize,
+    client: &impl TelegramApi,
+    profile: &str,
+    ignore_file_path: &Path,
+) -> Result<(), Error> {
+    let mut chats: Vec<PackedChat> = match config.report {
+        Some(ref path) => Report::load(path, profile)?
+            .chats
+            .iter()
+            .filter(|chat| !chat.left)
+            .filter_map(|chat| chat.packed())
+            .collect(),
+        None => {
+            let ignored = IgnoreList::load(ignore_file_path)?.matcher()?;
+
+            println!("Analyze the chats you are a member of. It may take a few minutes.");
+
+            analyze::get_chats(client, &ignored, &ChatFilter::default(), &Rules::default())
+                .await?
+                .iter()
+                .filter_map(Chat::packed)
+                .collect()
+        }
+    };
+
+    if chats.is_empty() {
+        println!(
+            "No chats are found, folder `{folder}` isn't changed",
+            folder = config.folder
+        );
+        return Ok(());
+    }
+    if chats.len() > folders::FOLDER_CHATS_LIMIT {
+        println!(
+            "Found {count} chats, but a folder fits only {limit} of them. The rest are skipped.",
+            count = chats.len(),
+            limit = folders::FOLDER_CHATS_LIMIT,
+        );
+        chats.truncate(folders::FOLDER_CHATS_LIMIT);
+    }
+
+    let existing = folders::find_folder(client, &config.folder).await?.ok();
+
+    // A folder made by hand shouldn't lose its chats silently
+    if let Some(ref folder) = existing {
+        if !config.force && !folder.is_subset_of(&chats) {
+            return Err(Error::FolderReplace {
+                name: folder.title.clone(),
+            });
+        }
+    }
+
+    // The folder is found case-insensitively, so the title of the existing one is kept
+    let title = existing
+        .as_ref()
+        .map_or(config.folder.as_str(), |folder| &folder.title);
+    let created = client.save_folder(title, &chats).await?;
+
+    println!(
+        "Folder `{title}` is {action} with {count} chat(s)",
+        action = if created { "created" } else { "updated" },
+        count = chats.len(),
+    );
+
+    Ok(())
+}
+
+pub fn encrypt_session(config: &ClientConfig) -> Result<(), SessionError> {
+    session_file::encrypt_existing(config)?;
+
+    println!(
+        "Session file `{path}` is encrypted. The passphrase will be asked at each run unless it's set by `TGOCM_SESSION_PASSPHRASE` or `session_passphrase_file`.",
+        path = config.session_file_path().display(),
+    );
+
+    Ok(())
+}
+
+pub fn ignore(command: IgnoreCommands, ignore_file_path: &Path) -> Result<(), IgnoreError> {
+    let mut ignore_list = IgnoreList::load(ignore_file_path)?;
+
+    let changed = match command {
+        IgnoreCommands::Add(entry) => match entry {
+            IgnoreEntry { id: Some(id), .. } => ignore_list.ids.insert(id),
+            IgnoreEntry {
+                username: Some(username),
+                ..
+            } => ignore_list.add_username(&username),
+            IgnoreEntry {
+                title_pattern: Some(pattern),
+                ..
+            } => {
+                let added = ignore_list.title_patterns.insert(pattern.into());
+                // Check the pattern before it's saved
+                ignore_list.matcher()?;
+                added
+            }
+            IgnoreEntry { .. } => unreachable!("One of the entry arguments is required"),
+        },
+        IgnoreCommands::Remove(entry) => match entry {
+            IgnoreEntry { id: Some(id), .. } => ignore_list.ids.remove(&id),
+            IgnoreEntry {
+                username: Some(username),
+                ..
+            } => ignore_list.remove_username(&username),
+            IgnoreEntry {
+                title_pattern: Some(pattern),
+                ..
+            } => ignore_list.title_patterns.remove(&*pattern),
+            IgnoreEntry { .. } => unreachable!("One of the entry arguments is required"),
+        },
+        IgnoreCommands::List => {
+            if ignore_list.is_empty() {
+                println!(
+                    "Ignore list `{path}` is empty",
+                    path = ignore_file_path.display(),
+                );
+                return Ok(());
+            }
+
+            println!("Ignore list `{path}`:", path = ignore_file_path.display());
+            for id in &ignore_list.ids {
+                println!("  id {id}");
+            }
+            for username in &ignore_list.usernames {
+                println!("  username @{username}");
+            }
+            for pattern in &ignore_list.title_patterns {
+                println!("  title `{pattern}`");
+            }
+            return Ok(());
+        }
+    };
+
+    if changed {
+        ignore_list.save(ignore_file_path)?;
+        println!(
+            "Ignore list `{path}` is updated",
+            path = ignore_file_path.display(),
+        );
+    } else {
+        println!("Ignore list isn't changed");
+    }
+
+    Ok(())
+}
+
+/// The config is checked before it's parsed for the run, so a config that can't be parsed is
+/// reported as a problem too
+pub fn check_config(
+    raw: &str,
+    overrides: &Overrides,
+    config_file_path: PathBuf,
+) -> Result<(), Error> {
+    let problems = match configs::check(raw, overrides) {
+        Ok(problems) => problems.iter().map(ToString::to_string).collect(),
+        Err(err) => vec![format!("The config can't be parsed: {err}")],
+    };
+    if problems.is_empty() {
+        println!(
+            "Config file `{path}` is valid",
+            path = config_file_path.display(),
+        );
+        return Ok(());
+    }
+
+    for problem in &problems {
+        println!("* {problem}");
+    }
+
+    Err(Error::ConfigCheck {
+        path: config_file_path,
+        problems: problems.len(),
+    })
+}
+
+/// Read the value until it's valid
+fn prompt<T>(prompt: &str, parse: impl Fn(&str) -> Result<T, String>) -> Result<T, Error> {
+    loop {
+        let input = auth::read_line(prompt).map_err(Error::Input)?;
+
+        match parse(&input) {
+            Ok(value) => return Ok(value),
+            Err(message) => println!("Invalid value: {message}"),
+        }
+    }
+}
+
+fn confirm(prompt: &str) -> Result<bool, Error> {
+    let input = auth::read_line(&format!("{prompt} [y/N]: ")).map_err(Error::Input)?;
+
+    Ok(matches!(&*input, "y" | "Y" | "yes" | "Yes"))
+}
+
+fn create_parent_dir(path: &Path) -> Result<(), Error> {
+    match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => {
+            fs::create_dir_all(dir).map_err(|source| Error::ConfigWrite {
+                path: path.to_path_buf(),
+                source,
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+pub fn init_config(config_file_path: PathBuf) -> Result<(), Error> {
+    if config_file_path.exists()
+        && !confirm(&format!(
+            "Config file `{path}` already exists. Overwrite it?",
+            path = config_file_path.display(),
+        ))?
+    {
+        println!("Config file isn't changed");
+        return Ok(());
+    }
+
+    println!("Get `api_id` and `api_hash` from https://my.telegram.org/apps");
+
+    let api_id = prompt("api_id: ", |input| {
+        let api_id = input
+            .parse()
+            .map_err(|_| format!("`{input}` must be a number"))?;
+        check_api_id(api_id).map(|()| api_id)
+    })?;
+    let api_hash = prompt("api_hash: ", |input| {
+        check_api_hash(input).map(|()| input.into())
+    })?;
+    let phone_number = prompt(
+        "Phone number (optional, leave empty to log in by QR code): ",
+        |input| check_phone_number(input).map(|()| (!input.is_empty()).then(|| input.into())),
+    )?;
+    let password = rpassword::prompt_password(
+        "Password of your 2FA (optional, leave empty to enter it at login): ",
+    )
+    .map_err(Error::Input)?;
+    let default_path = default_session_file_path(DEFAULT_PROFILE_NAME);
+    let session_file_path = prompt(
+        &format!(
+            "Session file path (default: {path}): ",
+            path = default_path.display(),
+        ),
+        |input| {
+            let path = if input.is_empty() {
+                default_path.clone()
+            } else {
+                PathBuf::from(input)
+            };
+            check_session_file_path(&path).map(|()| path)
+        },
+    )?;
+
+    let template = Template {
+        api_id,
+        api_hash,
+        phone_number,
+        password: (!password.is_empty()).then(|| password.into()),
+        session_file_path,
+    };
+
+    create_parent_dir(&config_file_path)?;
+    fs::write(&config_file_path, template.render()).map_err(|source| Error::ConfigWrite {
+        path: config_file_path.clone(),
+        source,
+    })?;
+
+    println!(
+        "Config file `{path}` is created. Run `config check` to check it at any time.",
+        path = config_file_path.display(),
+    );
+
+    Ok(())
+}
+
+pub async fn session_status(client: &Client, config: &ClientConfig) -> Result<(), Error> {
+    let path = config.session_file_path();
+    if session_file::is_file_encrypted(path)? {
+        println!("Session file: {path} (encrypted)", path = path.display());
+    } else {
+        println!("Session file: {path}", path = path.display());
+    }
+
+    if !client.is_authorized().await? {
+        println!("You aren't logged in");
+        return Ok(());
+    }
+
+    let me = client.get_me().await?;
+    if let Some(username) = me.username() {
+        println!(
+            "Logged in as {name} (@{username}, id={id})",
+            name = me.full_name(),
+            id = me.id(),
+        );
+    } else {
+        println!(
+            "Logged in as {name} (id={id})",
+            name = me.full_name(),
+            id = me.id(),
+        );
+    }
+    if let Some(phone) = me.phone() {
+        println!("Phone number: +{phone}");
+    }
+    println!("DC: {dc}", dc = session::get_current_dc(client).await?);
+
+    Ok(())
+}
+
+pub async fn session_logout(client: &Client, config: &ClientConfig) -> Result<(), Error> {
+    if client.is_authorized().await? {
+        client.sign_out().await?;
+
+        println!("You have logged out");
+    } else {
+        println!("You aren't logged in");
+    }
+
+    session_file::remove(config.session_file_path())?;
+
+    println!(
+        "Session file `{path}` is removed",
+        path = config.session_file_path().display(),
+    );
+
+    Ok(())
+}
+
+pub async fn session_list(client: &Client) -> Result<(), InvocationError> {
+    for authorization in session::get_authorizations(client).await? {
+        println!(
+            "{hash}{current}: {app_name} {app_version} on {device_model} ({platform} {system_version}), ip={ip} ({country}), last active {date_active}",
+            hash = authorization.hash,
+            current = if authorization.current { " (current)" } else { "" },
+            app_name = authorization.app_name,
+            app_version = authorization.app_version,
+            device_model = authorization.device_model,
+            platform = authorization.platform,
+            system_version = authorization.system_version,
+            ip = authorization.ip,
+            country = authorization.country,
+            date_active = DateTime::from_timestamp(authorization.date_active.into(), 0)
+                .map_or("unknown".to_owned(), |date| date.to_string()),
+        );
+    }
+
+    Ok(())
+}
+
+pub async fn session_terminate(config: Terminate, client: &Client) -> Result<(), InvocationError> {
+    if config.hash == 0 {
+        println!("It's the current session. Use `session logout` command to terminate it.");
+        return Ok(());
+    }
+
+    match session::reset_authorization(client, config.hash).await {
+        Ok(true) => {
+            println!("The session is terminated");
+            Ok(())
+        }
+        Ok(false) => {
+            println!("The session isn't terminated");
+            Ok(())
+        }
+        Err(InvocationError::Rpc(RpcError {
+            code: _code @ 400, ..
+        })) => {
+            println!("Session not found. Probably specified incorrect hash, check it by `session list` command.");
+            Ok(())
+        }
+        Err(err) => Err(err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::api::{
+        fake::{self, Call, FakeApi, TempFile},
+        LeftChat,
+    };
+
+    /// Report of the `default` profile with an old joined chat, an old left chat and an empty
+    /// joined chat
+    async fn save_report(api: &FakeApi, name: &str) -> TempFile {
+        let ignored = IgnoreList::default().matcher().unwrap();
+        let mut chats =
+            analyze::get_chats(api, &ignored, &ChatFilter::default(), &Rules::default())
+                .await
+                .unwrap();
+        chats.extend(
+            analyze::get_left_chats(api, 1, &ignored, &ChatFilter::default())
+                .await
+                .unwrap(),
+        );
+
+        let report = TempFile::new(name);
+        Report::new(Some("default"), &chats)
+            .save(report.path())
+            .unwrap();
+        report
+    }
+
+    fn api() -> FakeApi {
+        FakeApi::default()
+            .with_dialog(
+                fake::chat(PackedType::Megagroup, 1, "Old supergroup"),
+                fake::old_messages(),
+            )
+            .with_dialog(fake::chat(PackedType::Chat, 2, "Empty group"), vec![])
+            .with_left_chat(
+                LeftChat::Chat(fake::chat(PackedType::Megagroup, 3, "Left supergroup")),
+                fake::old_messages(),
+            )
+    }
+
+    #[tokio::test]
+    async fn chats_from_report_you_left_are_skipped() {
+        let api = api();
+        let report = save_report(&api, "archive").await;
+
+        let target = || ChatsTarget {
+            id: None,
+            access_hash: None,
+            report: Some(report.path().to_owned()),
+        };
+        archive_chats(target(), &api, "default").await.unwrap();
+        mute_chats(target(), &api, "default").await.unwrap();
+
+        assert_eq!(
+            api.calls(),
+            [
+                Call::Archive(1),
+                Call::Archive(2),
+                Call::Mute(1),
+                Call::Mute(2)
+            ],
+        );
+    }
+
+    #[tokio::test]
+    async fn report_of_another_profile_is_refused() {
+        let api = api();
+        let report = save_report(&api, "other-profile").await;
+
+        let target = ChatsTarget {
+            id: None,
+            access_hash: None,
+            report: Some(report.path().to_owned()),
+        };
+        let result = archive_chats(target, &api, "work").await;
+
+        assert!(matches!(
+            result,
+            Err(Error::Report(analyze::ReportError::OtherProfile { .. }))
+        ));
+        assert_eq!(api.calls(), []);
+    }
+
+    #[tokio::test]
+    async fn report_of_all_profiles_is_split_by_profile() {
+        let api = api();
+        let ignored = IgnoreList::default().matcher().unwrap();
+        let chats = analyze::get_chats(&api, &ignored, &ChatFilter::default(), &Rules::default())
+            .await
+            .unwrap();
+        let (work, personal) = chats.split_at(1);
+
+        let report = TempFile::new("all-profiles");
+        Report::merged([("work", work), ("personal", personal)])
+            .save(report.path())
+            .unwrap();
+
+        let target = ChatsTarget {
+            id: None,
+            access_hash: None,
+            report: Some(report.path().to_owned()),
+        };
+        let result = archive_chats(target, &api, "personal").await;
+
+        result.unwrap();
+        assert_eq!(api.calls(), [Call::Archive(2)]);
+    }
+
+    #[tokio::test]
+    async fn target_without_chat_is_an_error() {
+        let api = api();
+
+        let target = ChatsTarget {
+            id: None,
+            access_hash: None,
+            report: None,
+        };
+        let result = mute_chats(target, &api, "default").await;
+
+        assert!(matches!(result, Err(Error::ChatNotSpecified)));
+        assert_eq!(api.calls(), []);
+    }
+
+    #[tokio::test]
+    async fn invalid_channel_is_reported() {
+        let mut api = api();
+        api.invalid_chats.insert(3);
+
+        let config = |id| Join {
+            id,
+            access_hash: None,
+        };
+        join_channel(config(3), &api).await.unwrap();
+        join_channel(config(1), &api).await.unwrap();
+
+        assert_eq!(api.calls(), [Call::Join(1)]);
+    }
+
+    #[tokio::test]
+    async fn existing_folder_gets_chats_replaced() {
+        let api = api();
+        api.folders.borrow_mut().push(("Stale".into(), vec![]));
+        let report = save_report(&api, "organize").await;
+
+        let config = Organize {
+            folder: "stale".to_owned(),
+            report: Some(report.path().to_owned()),
+            force: false,
+        };
+        organize(&config, &api, "default", Path::new("unused.toml"))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            api.calls(),
+            [Call::SaveFolder {
+                title: "Stale".into(),
+                ids: vec![1, 2],
+            }],
+        );
+        let folders = api.folders.borrow();
+        assert_eq!(folders.len(), 1);
+        assert_eq!(&*folders[0].0, "Stale");
+    }
+
+    #[tokio::test]
+    async fn folder_with_other_chats_is_replaced_only_by_force() {
+        let api = api();
+        let kept = PackedChat {
+            ty: PackedType::Broadcast,
+            id: 5,
+            access_hash: Some(50),
+        };
+        api.folders.borrow_mut().push(("Stale".into(), vec![kept]));
+        let report = save_report(&api, "organize-force").await;
+
+        let config = |force| Organize {
+            folder: "Stale".to_owned(),
+            report: Some(report.path().to_owned()),
+            force,
+        };
+        let refused = organize(&config(false), &api, "default", Path::new("unused.toml")).await;
+        let forced = organize(&config(true), &api, "default", Path::new("unused.toml")).await;
+
+        assert!(matches!(refused, Err(Error::FolderReplace { .. })));
+        forced.unwrap();
+        assert_eq!(
+            api.calls(),
+            [Call::SaveFolder {
+                title: "Stale".into(),
+                ids: vec![1, 2],
+            }],
+        );
+    }
+}
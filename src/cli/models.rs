@@ -1,5 +1,6 @@
-use clap::{builder::Styles, command, Args, Parser, Subcommand};
+use clap::{builder::Styles, command, value_parser, ArgGroup, Args, Parser, Subcommand, ValueEnum};
 use clap_cargo::style;
+use regex::Regex;
 use std::path::PathBuf;
 
 pub const CLAP_STYLING: Styles = Styles::styled()
@@ -15,17 +16,42 @@ pub const CLAP_STYLING: Styles = Styles::styled()
 #[command(styles = CLAP_STYLING)]
 #[command(version, about, long_about = None)]
 pub struct Cli {
-    #[arg(
-        short = 'c',
-        long = "config-path",
-        default_value = "configs/config.toml"
-    )]
-    pub config_file_path: PathBuf,
+    /// Config file. If unset, the first found of `$TGOCM_CONFIG`,
+    /// `$XDG_CONFIG_HOME/tg_old_chats_manager/config.toml` and `configs/config.toml` is used.
+    #[arg(short = 'c', long = "config-path")]
+    pub config_file_path: Option<PathBuf>,
+    /// Profile from `[profiles.<name>]` section of the config. `default` is `[client]` section.
+    #[arg(short = 'p', long = "profile", global = true)]
+    pub profile: Option<String>,
+    #[command(flatten)]
+    pub client_overrides: ClientOverrides,
 
     #[command(subcommand)]
     pub command: Commands,
 }
 
+// Client settings with the highest precedence: they override environment variables and the config.
+// It's a plain comment, because a doc comment of the flattened struct becomes the about of the app.
+#[derive(Args)]
+#[command(next_help_heading = "Client overrides")]
+pub struct ClientOverrides {
+    /// Override `api_id` of the profile
+    #[arg(long = "api-id", global = true)]
+    pub api_id: Option<i32>,
+    /// Override `api_hash` of the profile
+    #[arg(long = "api-hash", global = true)]
+    pub api_hash: Option<String>,
+    /// Override `phone_number` of the profile
+    #[arg(long = "phone-number", global = true)]
+    pub phone_number: Option<String>,
+    /// Read the password of 2FA of the profile from the file
+    #[arg(long = "password-file", global = true)]
+    pub password_file: Option<PathBuf>,
+    /// Override `session_file_path` of the profile
+    #[arg(long = "session-file-path", global = true)]
+    pub session_file_path: Option<PathBuf>,
+}
+
 #[derive(Subcommand)]
 pub enum Commands {
     /// Analyze chats
@@ -34,6 +60,113 @@ pub enum Commands {
     Join(Join),
     /// Delete chat
     Delete(Delete),
+    /// Archive chats
+    Archive(ChatsTarget),
+    /// Mute chats forever
+    Mute(ChatsTarget),
+    /// Put the found joined chats into a folder to review them in any Telegram app
+    Organize(Organize),
+    /// Manage the session file
+    Session(Session),
+    /// Manage the config file
+    Config(Config),
+    /// Manage chats that are never flagged by the analysis
+    Ignore(Ignore),
+}
+
+/// Command grouped by what it needs to run
+pub enum Command {
+    /// Creates the config file, so it's run before the config is read
+    ConfigInit,
+    /// Reports problems of the config, so it's run before the config is parsed
+    ConfigCheck,
+    Offline(OfflineCommand),
+    /// Analysis of each profile with its own client
+    AnalyzeAllProfiles(Box<Analyze>),
+    Connected(ConnectedCommand),
+}
+
+/// Commands that need the config, but not Telegram
+pub enum OfflineCommand {
+    SessionEncrypt,
+    Ignore(IgnoreCommands),
+    Replay {
+        analyze: Box<Analyze>,
+        fixture_path: PathBuf,
+    },
+    Export {
+        analyze: Box<Analyze>,
+        export_path: PathBuf,
+    },
+}
+
+/// Commands that need a client connected to Telegram
+pub enum ConnectedCommand {
+    Analyze(Box<Analyze>),
+    Join(Join),
+    Delete(Delete),
+    Archive(ChatsTarget),
+    Mute(ChatsTarget),
+    Organize(Organize),
+    SessionStatus,
+    SessionLogout,
+    SessionList,
+    SessionTerminate(Terminate),
+}
+
+impl ConnectedCommand {
+    /// Commands that work with unauthorized client too
+    pub const fn requires_authorization(&self) -> bool {
+        !matches!(
+            self,
+            ConnectedCommand::SessionStatus | ConnectedCommand::SessionLogout
+        )
+    }
+}
+
+impl From<Commands> for Command {
+    fn from(command: Commands) -> Self {
+        use ConnectedCommand as Connected;
+        use OfflineCommand as Offline;
+
+        match command {
+            Commands::Analyze(analyze) if analyze.all_profiles => {
+                Command::AnalyzeAllProfiles(Box::new(analyze))
+            }
+            Commands::Analyze(mut analyze) => {
+                match (analyze.replay.take(), analyze.from_export.take()) {
+                    (Some(fixture_path), _) => Command::Offline(Offline::Replay {
+                        analyze: Box::new(analyze),
+                        fixture_path,
+                    }),
+                    (None, Some(export_path)) => Command::Offline(Offline::Export {
+                        analyze: Box::new(analyze),
+                        export_path,
+                    }),
+                    (None, None) => Command::Connected(Connected::Analyze(Box::new(analyze))),
+                }
+            }
+            Commands::Join(join) => Command::Connected(Connected::Join(join)),
+            Commands::Delete(delete) => Command::Connected(Connected::Delete(delete)),
+            Commands::Archive(target) => Command::Connected(Connected::Archive(target)),
+            Commands::Mute(target) => Command::Connected(Connected::Mute(target)),
+            Commands::Organize(organize) => Command::Connected(Connected::Organize(organize)),
+            Commands::Session(Session { command }) => match command {
+                SessionCommands::Status => Command::Connected(Connected::SessionStatus),
+                SessionCommands::Logout => Command::Connected(Connected::SessionLogout),
+                SessionCommands::List => Command::Connected(Connected::SessionList),
+                SessionCommands::Terminate(terminate) => {
+                    Command::Connected(Connected::SessionTerminate(terminate))
+                }
+                SessionCommands::Encrypt => Command::Offline(Offline::SessionEncrypt),
+            },
+            Commands::Config(Config { command }) => match command {
+                ConfigCommands::Init => Command::ConfigInit,
+                ConfigCommands::Check => Command::ConfigCheck,
+            },
+            Commands::Ignore(Ignore { command }) => Command::Offline(Offline::Ignore(command)),
+        }
+    }
 }
 
 #[derive(Debug, Args)]
@@ -44,6 +177,117 @@ pub struct Analyze {
     /// Analyze left chats
     #[arg(short = 'l', long = "left", default_value = "false")]
     pub left: bool,
+    /// Analyze chats of all profiles from the config. Overrides of the account, like the phone
+    /// number, can't be used with it.
+    #[arg(
+        long = "all-profiles",
+        default_value = "false",
+        conflicts_with_all = ["profile", "phone_number", "password_file", "session_file_path"]
+    )]
+    pub all_profiles: bool,
+    /// Review the found chats in the terminal UI and join, leave, delete or archive them
+    #[arg(long = "tui", default_value = "false", conflicts_with = "all_profiles")]
+    pub tui: bool,
+    /// Analyze only chats of these kinds
+    #[arg(long = "kind", value_delimiter = ',')]
+    pub kinds: Vec<ChatKind>,
+    /// Analyze only chats with titles matching the regular expression
+    #[arg(long = "title-regex", value_parser = Regex::new)]
+    pub title_regex: Option<Regex>,
+    /// Analyze only the chat with this username
+    #[arg(long = "username")]
+    pub username: Option<String>,
+    /// Analyze only chats with at least this members count. Chats with the unknown count are kept.
+    #[arg(long = "min-members")]
+    pub min_members: Option<i32>,
+    /// Analyze only chats with at most this members count. Chats with the unknown count are kept.
+    #[arg(long = "max-members")]
+    pub max_members: Option<i32>,
+    /// Analyze only joined chats in the folder with this name
+    #[arg(long = "folder")]
+    pub folder: Option<String>,
+    /// Analyze only archived chats
+    #[arg(
+        long = "archived-only",
+        default_value = "false",
+        conflicts_with = "exclude_archived"
+    )]
+    pub archived_only: bool,
+    /// Don't analyze archived chats
+    #[arg(long = "exclude-archived", default_value = "false")]
+    pub exclude_archived: bool,
+    /// Save the found chats to the JSON report to archive or mute them later
+    #[arg(long = "save-report")]
+    pub save_report: Option<PathBuf>,
+    /// Count messages per month over this number of 30-day months by sampling the history by
+    /// dates, and find chats that were quiet most of them, or three quarters of them for
+    /// supergroups and channels. It takes up to a request per month for each chat.
+    #[arg(
+        long = "activity-months",
+        value_name = "MONTHS",
+        value_parser = value_parser!(u16).range(1..)
+    )]
+    pub activity_months: Option<u16>,
+    /// Find groups you haven't written to for this number of days, however active they're. It
+    /// takes a request for each group that isn't found by other rules.
+    #[arg(
+        long = "own-message-days",
+        value_name = "DAYS",
+        value_parser = value_parser!(u16).range(1..)
+    )]
+    pub own_message_days: Option<u16>,
+    /// Find chats with at least this number of unread messages if you haven't read them for 30
+    /// days, or 60 for supergroups and channels. It takes a request for each such chat.
+    #[arg(
+        long = "min-unread",
+        value_name = "COUNT",
+        value_parser = value_parser!(i32).range(1..)
+    )]
+    pub min_unread: Option<i32>,
+    /// Fetch members of joined chats to find the ones with only you and bots left or without
+    /// admins. It takes up to two requests for each chat that isn't found by other rules.
+    #[arg(long = "members", default_value = "false")]
+    pub members: bool,
+    /// Keep member counts of joined chats in the JSON file between analyses to find the ones that
+    /// lost a quarter of their members.
+    #[arg(long = "members-history", value_name = "PATH", requires = "members")]
+    pub members_history: Option<PathBuf>,
+    /// Record the Telegram responses to the fixture file with anonymized IDs, titles and texts.
+    /// Replay it to reproduce the analysis without the account.
+    #[arg(long = "record", conflicts_with = "all_profiles")]
+    pub record: Option<PathBuf>,
+    /// Analyze the responses from the fixture file instead of Telegram. It works offline.
+    #[arg(
+        long = "replay",
+        conflicts_with_all = ["all_profiles", "record", "tui", "save_report", "members_history"]
+    )]
+    pub replay: Option<PathBuf>,
+    /// Analyze the chats from `result.json` of the Telegram Desktop export in JSON format instead
+    /// of Telegram. It works offline.
+    #[arg(
+        long = "from-export",
+        conflicts_with_all = [
+            "all_profiles",
+            "record",
+            "replay",
+            "tui",
+            "save_report",
+            "members_history",
+        ]
+    )]
+    pub from_export: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum ChatKind {
+    /// Basic group
+    Group,
+    /// Supergroup
+    Megagroup,
+    /// Channel
+    Broadcast,
+    /// Broadcast group
+    Gigagroup,
 }
 
 #[derive(Debug, Args)]
@@ -56,6 +300,33 @@ pub struct Join {
     pub access_hash: Option<i64>,
 }
 
+#[derive(Debug, Args)]
+#[command(group(ArgGroup::new("target").required(true).args(["id", "report"])))]
+pub struct ChatsTarget {
+    /// Chat/channel ID
+    #[arg(short = 'i', long = "id")]
+    pub id: Option<i64>,
+    /// Access hash of the channel/supergroup. Omit it for basic groups.
+    #[arg(short = 'a', long = "access-hash", conflicts_with = "report")]
+    pub access_hash: Option<i64>,
+    /// Report saved by `analyze --save-report`. Chats you aren't a member of are skipped.
+    #[arg(short = 'r', long = "report")]
+    pub report: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub struct Organize {
+    /// Folder to create or to replace the chats of
+    #[arg(short = 'f', long = "folder", default_value = "Stale")]
+    pub folder: String,
+    /// Take the chats from the report saved by `analyze --save-report` instead of analyzing them
+    #[arg(short = 'r', long = "report")]
+    pub report: Option<PathBuf>,
+    /// Replace chats of the existing folder even if it has rules or chats that aren't found
+    #[arg(long = "force", default_value = "false")]
+    pub force: bool,
+}
+
 #[derive(Debug, Args)]
 pub struct Delete {
     /// Channel/supergroup ID to delete
@@ -65,3 +336,74 @@ pub struct Delete {
     #[arg(short = 'a', long = "access-hash")]
     pub access_hash: Option<i64>,
 }
+
+#[derive(Debug, Args)]
+pub struct Session {
+    #[command(subcommand)]
+    pub command: SessionCommands,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum SessionCommands {
+    /// Show the logged-in user, DC and session file
+    Status,
+    /// Log out and remove the session file
+    Logout,
+    /// List active sessions of the account
+    List,
+    /// Terminate other active session of the account
+    Terminate(Terminate),
+    /// Encrypt the existing plaintext session file with a passphrase
+    Encrypt,
+}
+
+#[derive(Debug, Args)]
+pub struct Terminate {
+    /// Hash of the session from `session list` command
+    #[arg(long = "hash", allow_negative_numbers = true)]
+    pub hash: i64,
+}
+
+#[derive(Debug, Args)]
+pub struct Config {
+    #[command(subcommand)]
+    pub command: ConfigCommands,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ConfigCommands {
+    /// Create the config file interactively
+    Init,
+    /// Check the config file offline and print all problems
+    Check,
+}
+
+#[derive(Debug, Args)]
+pub struct Ignore {
+    #[command(subcommand)]
+    pub command: IgnoreCommands,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum IgnoreCommands {
+    /// Add a chat to the ignore list
+    Add(IgnoreEntry),
+    /// Remove a chat from the ignore list
+    Remove(IgnoreEntry),
+    /// Show the ignore list
+    List,
+}
+
+#[derive(Debug, Args)]
+#[group(required = true, multiple = false)]
+pub struct IgnoreEntry {
+    /// Chat ID
+    #[arg(short = 'i', long = "id", allow_negative_numbers = true)]
+    pub id: Option<i64>,
+    /// Chat username with or without `@`
+    #[arg(short = 'u', long = "username")]
+    pub username: Option<String>,
+    /// Case-insensitive regular expression of the chat title
+    #[arg(short = 't', long = "title")]
+    pub title_pattern: Option<String>,
+}
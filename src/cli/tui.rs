@@ -0,0 +1,387 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    io,
+};
+
+use grammers_session::PackedType;
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Text},
+    widgets::{Block, List, ListItem, ListState, Paragraph, Wrap},
+    DefaultTerminal, Frame,
+};
+
+use crate::client::analyze::{Chat, DAYS_IN_MONTH};
+
+const PAGE_SIZE: u16 = 10;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Join,
+    Leave,
+    Delete,
+    Archive,
+    Ignore,
+}
+
+impl Action {
+    const fn mark(self) -> char {
+        match self {
+            Action::Join => 'J',
+            Action::Leave => 'L',
+            Action::Delete => 'D',
+            Action::Archive => 'A',
+            Action::Ignore => 'I',
+        }
+    }
+
+    /// Join is possible only for left channels, leave and archive only for chats you're a member
+    /// of, delete only for channels. Empty chats can only be ignored.
+    pub fn is_available(self, chat: &Chat) -> bool {
+        let Some(packed) = chat.packed() else {
+            return self == Action::Ignore;
+        };
+        let is_channel = packed.ty != PackedType::Chat;
+
+        match self {
+            Action::Join => chat.is_left() && is_channel,
+            Action::Leave | Action::Archive => !chat.is_left(),
+            Action::Delete => is_channel,
+            Action::Ignore => true,
+        }
+    }
+}
+
+impl Display for Action {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Action::Join => write!(f, "join"),
+            Action::Leave => write!(f, "leave"),
+            Action::Delete => write!(f, "delete"),
+            Action::Archive => write!(f, "archive"),
+            Action::Ignore => write!(f, "ignore"),
+        }
+    }
+}
+
+/// Analyzed chat with the action marked for it
+pub struct Item {
+    pub chat: Chat,
+    pub link: Option<Box<str>>,
+    pub action: Option<Action>,
+}
+
+impl Item {
+    fn title(&self) -> String {
+        match self.chat.title() {
+            Some(title) => title.to_owned(),
+            None => format!("Chat {id}", id = self.chat.id()),
+        }
+    }
+
+    fn details(&self) -> Text<'static> {
+        let chat = &self.chat;
+
+        let mut lines = vec![
+            Line::from(format!("Title: {title}", title = self.title())),
+            Line::from(format!("Reason: {reason}", reason = chat.reason())),
+            Line::from(format!(
+                "Status: {status}",
+                status = if chat.is_left() { "left" } else { "member" },
+            )),
+            Line::from(format!("ID: {id}", id = chat.id())),
+            Line::from(format!(
+                "Access hash: {access_hash}",
+                access_hash = chat
+                    .access_hash()
+                    .map_or("unknown".to_owned(), |access_hash| access_hash.to_string()),
+            )),
+            Line::from(format!(
+                "Invite link: {link}",
+                link = self.link.as_deref().unwrap_or("none"),
+            )),
+            Line::from(format!(
+                "Last activity: {date}",
+                date = chat
+                    .messages()
+                    .first()
+                    .map_or("unknown".to_owned(), |message| message.0.date.to_string()),
+            )),
+        ];
+
+        if let Some(members) = chat.members() {
+            lines.push(Line::from(format!(
+                "Members: {count}, bots: {bots}, admins: {admins}",
+                count = members
+                    .count
+                    .map_or("unknown".to_owned(), |count| count.to_string()),
+                bots = members.bots,
+                admins = members
+                    .admins
+                    .map_or("unknown".to_owned(), |admins| admins.to_string()),
+            )));
+        }
+
+        if let Some(unread_count) = chat.unread_count() {
+            lines.push(Line::from(format!("Unread messages: {unread_count}")));
+        }
+
+        if let Some(stats) = chat.stats() {
+            lines.push(Line::default());
+            lines.push(Line::from(format!(
+                "Messages: {total}",
+                total = stats.total
+            )));
+            lines.push(Line::from(format!(
+                "Messages in the last 7/30/90/365 days: {last_7_days}/{last_30_days}/{last_90_days}/{last_365_days}",
+                last_7_days = stats.last_7_days,
+                last_30_days = stats.last_30_days,
+                last_90_days = stats.last_90_days,
+                last_365_days = stats.last_365_days,
+            )));
+            lines.push(Line::from(format!(
+                "Last message: {date}",
+                date = stats
+                    .last_message_date
+                    .map_or("never".to_owned(), |date| date.to_string()),
+            )));
+            lines.push(Line::from(format!(
+                "Your last message: {date}",
+                date = stats
+                    .own_last_message_date
+                    .map_or("never".to_owned(), |date| date.to_string()),
+            )));
+            lines.push(Line::from(format!(
+                "Senders of the last messages: {senders}",
+                senders = stats.senders,
+            )));
+        }
+
+        if !chat.messages().is_empty() {
+            lines.push(Line::default());
+            lines.push(Line::from("Sample messages:"));
+
+            for message in chat.messages() {
+                let text = &*message.0.text;
+                let content = if text.is_empty() {
+                    format!("{action:?}", action = message.0.action)
+                } else {
+                    text.to_owned()
+                };
+
+                lines.push(Line::from(format!(
+                    "{date} #{id}: {content}",
+                    date = message.0.date,
+                    id = message.0.id,
+                )));
+            }
+        }
+
+        if let Some(activity) = chat.activity() {
+            lines.push(Line::default());
+            lines.push(Line::from("Messages per month:"));
+
+            for (index, month) in activity.months.iter().enumerate() {
+                lines.push(Line::from(format!(
+                    "{from}-{to} days ago: {month}",
+                    from = index as i64 * DAYS_IN_MONTH,
+                    to = (index as i64 + 1) * DAYS_IN_MONTH,
+                )));
+            }
+        }
+
+        Text::from(lines)
+    }
+}
+
+enum Screen {
+    List,
+    Confirm,
+}
+
+struct App<'a> {
+    items: &'a mut [Item],
+    state: ListState,
+    screen: Screen,
+    status: Option<String>,
+}
+
+impl App<'_> {
+    fn selected(&mut self) -> Option<&mut Item> {
+        self.state
+            .selected()
+            .and_then(|index| self.items.get_mut(index))
+    }
+
+    /// Mark the selected chat and move to the next one. Marking with the same action unmarks it.
+    fn mark(&mut self, action: Action) {
+        let Some(item) = self.selected() else {
+            return;
+        };
+
+        if !action.is_available(&item.chat) {
+            self.status = Some(format!("Can't {action} this chat"));
+            return;
+        }
+
+        if item.action == Some(action) {
+            item.action = None;
+        } else {
+            item.action = Some(action);
+            self.state.select_next();
+        }
+        self.status = None;
+    }
+
+    fn unmark(&mut self) {
+        if let Some(item) = self.selected() {
+            item.action = None;
+        }
+        self.status = None;
+    }
+
+    /// Returns `true` if the marked actions are confirmed
+    fn run(mut self, terminal: &mut DefaultTerminal) -> io::Result<bool> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+
+            match self.screen {
+                Screen::List => match key.code {
+                    KeyCode::Char('q') | KeyCode::Esc => return Ok(false),
+                    KeyCode::Down => self.state.select_next(),
+                    KeyCode::Up => self.state.select_previous(),
+                    KeyCode::PageDown => self.state.scroll_down_by(PAGE_SIZE),
+                    KeyCode::PageUp => self.state.scroll_up_by(PAGE_SIZE),
+                    KeyCode::Home => self.state.select_first(),
+                    KeyCode::End => self.state.select_last(),
+                    KeyCode::Char('j') => self.mark(Action::Join),
+                    KeyCode::Char('l') => self.mark(Action::Leave),
+                    KeyCode::Char('d') => self.mark(Action::Delete),
+                    KeyCode::Char('a') => self.mark(Action::Archive),
+                    KeyCode::Char('i') => self.mark(Action::Ignore),
+                    KeyCode::Char(' ') => self.unmark(),
+                    KeyCode::Enter => self.screen = Screen::Confirm,
+                    _ => {}
+                },
+                Screen::Confirm => match key.code {
+                    KeyCode::Char('y') => return Ok(true),
+                    KeyCode::Char('n') | KeyCode::Esc => self.screen = Screen::List,
+                    KeyCode::Char('q') => return Ok(false),
+                    _ => {}
+                },
+            }
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main, footer] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(2)]).areas(frame.area());
+
+        let help = match self.screen {
+            Screen::List => {
+                self.draw_list(frame, main);
+
+                "↑/↓ move  j join  l leave  d delete  a archive  i ignore  space unmark  enter confirm  q quit"
+            }
+            Screen::Confirm => {
+                self.draw_confirm(frame, main);
+
+                "y execute  n back  q quit without changes"
+            }
+        };
+
+        frame.render_widget(
+            Paragraph::new(vec![
+                Line::from(help),
+                Line::from(self.status.as_deref().unwrap_or_default().to_owned()),
+            ]),
+            footer,
+        );
+    }
+
+    fn draw_list(&mut self, frame: &mut Frame, area: Rect) {
+        let [list_area, details_area] =
+            Layout::horizontal([Constraint::Percentage(45), Constraint::Percentage(55)])
+                .areas(area);
+
+        let list = List::new(self.items.iter().map(|item| {
+            ListItem::new(format!(
+                "[{mark}] {reason}: {title}",
+                mark = item.action.map_or(' ', Action::mark),
+                reason = item.chat.reason(),
+                title = item.title(),
+            ))
+        }))
+        .block(Block::bordered().title(format!(" Chats ({count}) ", count = self.items.len())))
+        .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(list, list_area, &mut self.state);
+
+        let details = self
+            .state
+            .selected()
+            .and_then(|index| self.items.get(index))
+            .map(Item::details)
+            .unwrap_or_default();
+        frame.render_widget(
+            Paragraph::new(details)
+                .block(Block::bordered().title(" Details "))
+                .wrap(Wrap { trim: false }),
+            details_area,
+        );
+    }
+
+    fn draw_confirm(&self, frame: &mut Frame, area: Rect) {
+        let actions = self
+            .items
+            .iter()
+            .filter_map(|item| {
+                item.action.map(|action| {
+                    ListItem::new(format!(
+                        "{action} {title} ({id})",
+                        title = item.title(),
+                        id = item.chat.id(),
+                    ))
+                })
+            })
+            .collect::<Vec<_>>();
+
+        if actions.is_empty() {
+            frame.render_widget(
+                Paragraph::new("Nothing is marked. Press `n` to go back.")
+                    .block(Block::bordered().title(" Confirm ")),
+                area,
+            );
+        } else {
+            frame.render_widget(
+                List::new(actions).block(Block::bordered().title(" Execute the actions? ")),
+                area,
+            );
+        }
+    }
+}
+
+/// Show the chats to mark actions for them. Returns `true` if the marked actions are confirmed.
+pub fn review(items: &mut [Item]) -> io::Result<bool> {
+    let mut terminal = ratatui::try_init()?;
+
+    let result = App {
+        items,
+        state: ListState::default().with_selected(Some(0)),
+        screen: Screen::List,
+        status: None,
+    }
+    .run(&mut terminal);
+
+    ratatui::try_restore()?;
+
+    result
+}
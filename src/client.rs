@@ -1,5 +1,14 @@
 pub mod analyze;
+pub mod api;
+pub mod archive;
 pub mod auth;
 pub mod delete;
+pub mod folders;
 pub mod invite_link;
 pub mod join;
+pub mod leave;
+pub mod members;
+pub mod mute;
+pub mod search;
+pub mod session;
+pub mod session_file;
@@ -1,5 +1,16 @@
+mod activity;
+mod filter;
+mod members;
 mod models;
 mod polling;
+mod report;
+mod rules;
+mod stats;
 
+pub use activity::DAYS_IN_MONTH;
+pub use filter::{ArchiveFilter, ChatFilter};
+pub use members::{MembersHistory, MembersHistoryError};
 pub use models::Chat;
-pub use polling::{finish_takeout_session, get_chats, get_left_chats, init_takeout_session};
+pub use polling::{get_chats, get_left_chats};
+pub use report::{Report, ReportChatKind, ReportError};
+pub use rules::Rules;
@@ -0,0 +1,162 @@
+use std::fmt::{self, Display, Formatter};
+
+use chrono::{DateTime, Duration, Utc};
+use grammers_client::client::bots::InvocationError;
+use grammers_session::PackedChat;
+
+use crate::client::api::{Message, TelegramApi};
+
+/// Months of the histogram are counted back from now, so they don't match calendar months
+pub const DAYS_IN_MONTH: i64 = 30;
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MonthActivity {
+    pub messages: usize,
+    /// Whether the month has more messages than sampled, so `messages` is the lower bound
+    pub more: bool,
+}
+
+impl Display for MonthActivity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.more {
+            write!(f, "{messages}+", messages = self.messages)
+        } else {
+            write!(f, "{messages}", messages = self.messages)
+        }
+    }
+}
+
+/// Messages sent by people per month over the window, estimated by sampling the history by dates
+#[derive(Debug)]
+pub struct Activity {
+    /// The latest month first. The window ends at the month of the first message if the chat is
+    /// younger than it.
+    pub months: Box<[MonthActivity]>,
+}
+
+impl Activity {
+    pub fn active_months(&self) -> usize {
+        self.months
+            .iter()
+            .filter(|month| month.messages > 0)
+            .count()
+    }
+
+    /// Whether messages are sent in less than a half of the months. The share is divided by the
+    /// multiplier, since supergroups and channels are quiet longer than basic groups.
+    pub fn is_low(&self, multiplier: i64) -> bool {
+        (self.active_months() as i64) * 2 * multiplier < self.months.len() as i64
+    }
+}
+
+impl Display for Activity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Activity(per_month=[")?;
+        for (index, month) in self.months.iter().enumerate() {
+            if index == 0 {
+                write!(f, "{month}")?;
+            } else {
+                write!(f, ", {month}")?;
+            }
+        }
+        write!(f, "])")
+    }
+}
+
+fn month_index(message: &Message, now: DateTime<Utc>) -> usize {
+    ((now - message.date).num_days() / DAYS_IN_MONTH).max(0) as usize
+}
+
+/// Sample the history of the chat over the months back from now. `latest` are the last messages
+/// of the chat fetched with the same limit, the latest first.
+///
+/// Only human messages are counted, but the samples are paged by all the messages.
+/// A sample that ends inside a month marks it as having more messages, and the next sample
+/// starts from the end of the older month. So busy chats take a request per month, and quiet
+/// ones take a single request for the whole window. If the whole history is sampled, months
+/// before the first message of the chat aren't counted, since it doesn't exist then.
+pub async fn sample_activity(
+    client: &impl TelegramApi,
+    chat: PackedChat,
+    latest: &[Message],
+    limit: usize,
+    months: usize,
+    now: DateTime<Utc>,
+) -> Result<Activity, InvocationError> {
+    let mut activity = vec![MonthActivity::default(); months];
+    let mut sample = latest.to_vec();
+    // Month of the oldest sampled message
+    let mut first_month = None;
+
+    loop {
+        for message in sample.iter().filter(|message| message.is_human()) {
+            if let Some(month) = activity.get_mut(month_index(message, now)) {
+                month.messages += 1;
+            }
+        }
+        if let Some(oldest) = sample.last() {
+            first_month = Some(month_index(oldest, now));
+        }
+
+        // Whole history is sampled
+        if sample.len() < limit {
+            activity.truncate(first_month.map_or(0, |month| month + 1));
+            break;
+        }
+        let Some(oldest_month) = first_month else {
+            break;
+        };
+        let Some(month) = activity.get_mut(oldest_month) else {
+            break;
+        };
+        month.more = true;
+
+        if oldest_month + 1 == months {
+            break;
+        }
+        let offset_date = now - Duration::days(DAYS_IN_MONTH * (oldest_month as i64 + 1));
+        sample = client.get_messages_before(chat, offset_date, limit).await?;
+    }
+
+    Ok(Activity {
+        months: activity.into(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use grammers_session::PackedType;
+
+    use super::*;
+    use crate::client::api::fake::{self, FakeApi};
+
+    #[tokio::test]
+    async fn months_before_first_message_are_not_counted() {
+        let chat = fake::chat(PackedType::Megagroup, 1, "Young supergroup");
+        let messages = fake::messages(&[1, 2, 35, 36, 70, 71, 95]);
+        let api = FakeApi::default().with_dialog(chat.clone(), messages.clone());
+
+        let activity = sample_activity(&api, chat.packed(), &messages[..5], 5, 12, Utc::now())
+            .await
+            .unwrap();
+
+        assert_eq!(activity.months.len(), 4);
+        assert_eq!(activity.active_months(), 4);
+        assert!(!activity.is_low(1));
+    }
+
+    #[tokio::test]
+    async fn old_chat_is_sampled_over_the_whole_window() {
+        let chat = fake::chat(PackedType::Megagroup, 1, "Quiet supergroup");
+        let messages = fake::messages(&[1, 200, 400]);
+        let api = FakeApi::default().with_dialog(chat.clone(), messages.clone());
+
+        let activity = sample_activity(&api, chat.packed(), &messages, 5, 12, Utc::now())
+            .await
+            .unwrap();
+
+        assert_eq!(activity.months.len(), 12);
+        assert_eq!(activity.active_months(), 2);
+        assert!(activity.is_low(1));
+    }
+}
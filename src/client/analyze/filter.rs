@@ -0,0 +1,105 @@
+use grammers_session::PackedType;
+use regex::Regex;
+
+use crate::client::{
+    api::{ChatInfo, Dialog, LeftChat},
+    folders::{Folder, ARCHIVE_FOLDER_ID},
+};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ArchiveFilter {
+    #[default]
+    Any,
+    Only,
+    Exclude,
+}
+
+/// Chats to analyze. The filters are checked before the chat history is fetched, so they also
+/// reduce the number of requests. Empty filters match any chat.
+#[derive(Default)]
+pub struct ChatFilter {
+    pub kinds: Vec<PackedType>,
+    pub title: Option<Regex>,
+    /// Without `@`, it's compared case-insensitively
+    pub username: Option<Box<str>>,
+    /// Chats with the unknown members count aren't filtered out by the bounds
+    pub min_members: Option<i32>,
+    pub max_members: Option<i32>,
+    /// Only joined chats can be in a folder
+    pub folder: Option<Folder>,
+    /// Only joined chats can be archived
+    pub archive: ArchiveFilter,
+}
+
+impl ChatFilter {
+    /// Main list is walked unless only archived chats are analyzed
+    pub fn includes_main_list(&self) -> bool {
+        self.archive != ArchiveFilter::Only
+    }
+
+    /// Archive is walked unless archived chats are excluded
+    pub fn includes_archive(&self) -> bool {
+        self.archive != ArchiveFilter::Exclude
+    }
+
+    fn matches(
+        &self,
+        ty: PackedType,
+        title: &str,
+        username: Option<&str>,
+        members: Option<i32>,
+    ) -> bool {
+        (self.kinds.is_empty() || self.kinds.contains(&ty))
+            && self
+                .title
+                .as_ref()
+                .is_none_or(|regex| regex.is_match(title))
+            && self.username.as_deref().is_none_or(|expected| {
+                username.is_some_and(|username| username.eq_ignore_ascii_case(expected))
+            })
+            && members.is_none_or(|members| {
+                self.min_members.is_none_or(|min| members >= min)
+                    && self.max_members.is_none_or(|max| members <= max)
+            })
+    }
+
+    fn matches_chat(&self, chat: &ChatInfo) -> bool {
+        self.matches(chat.ty, &chat.title, chat.username.as_deref(), chat.members)
+    }
+
+    /// Dialog from the main list or a peer folder
+    pub fn matches_dialog(&self, dialog: &Dialog) -> bool {
+        let archived = dialog.folder_id == Some(ARCHIVE_FOLDER_ID);
+        let archive_matches = match self.archive {
+            ArchiveFilter::Any => true,
+            ArchiveFilter::Only => archived,
+            ArchiveFilter::Exclude => !archived,
+        };
+
+        archive_matches
+            && self
+                .folder
+                .as_ref()
+                .is_none_or(|folder| folder.contains(dialog))
+            && self.matches_chat(&dialog.chat)
+    }
+
+    /// Left chats aren't in any folder and aren't archived, so they never match these filters
+    pub fn matches_left(&self, chat: &LeftChat) -> bool {
+        if self.folder.is_some() || self.archive == ArchiveFilter::Only {
+            return false;
+        }
+
+        match chat {
+            LeftChat::Chat(chat) => self.matches_chat(chat),
+            // Nothing is known about empty chats, so they're kept only without filters
+            LeftChat::Empty { .. } => {
+                self.kinds.is_empty()
+                    && self.title.is_none()
+                    && self.username.is_none()
+                    && self.min_members.is_none()
+                    && self.max_members.is_none()
+            }
+        }
+    }
+}
@@ -0,0 +1,192 @@
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fmt::{self, Display, Formatter},
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::client::api::Members;
+
+/// Share of the peak members in percent a chat must keep to not be found as shrinking
+const KEPT_MEMBERS_PERCENT: i64 = 75;
+/// The oldest snapshots are dropped, so the peak is counted over the last analyses
+const SNAPSHOTS_LIMIT: usize = 30;
+
+#[derive(Debug)]
+pub enum MembersHistoryError {
+    Read {
+        path: PathBuf,
+        source: io::Error,
+    },
+    Parse {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+    Write {
+        path: PathBuf,
+        source: io::Error,
+    },
+    Serialize(serde_json::Error),
+}
+
+impl Display for MembersHistoryError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            MembersHistoryError::Read { path, source } => write!(
+                f,
+                "Can't read members history `{path}`: {source}",
+                path = path.display(),
+            ),
+            MembersHistoryError::Parse { path, source } => write!(
+                f,
+                "Members history `{path}` is invalid: {source}",
+                path = path.display(),
+            ),
+            MembersHistoryError::Write { path, source } => write!(
+                f,
+                "Can't write members history `{path}`: {source}",
+                path = path.display(),
+            ),
+            MembersHistoryError::Serialize(err) => {
+                write!(f, "Can't serialize members history: {err}")
+            }
+        }
+    }
+}
+
+impl Error for MembersHistoryError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            MembersHistoryError::Read { source, .. }
+            | MembersHistoryError::Write { source, .. } => Some(source),
+            MembersHistoryError::Parse { source, .. } | MembersHistoryError::Serialize(source) => {
+                Some(source)
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+struct Snapshot {
+    /// Unix timestamp
+    date: i64,
+    count: i32,
+}
+
+/// Member counts of joined chats from the previous analyses, kept in the file between them
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct MembersHistory {
+    /// By chat ID, the oldest first
+    chats: BTreeMap<i64, Vec<Snapshot>>,
+}
+
+impl MembersHistory {
+    /// The history is empty if the file doesn't exist yet
+    pub fn load(path: &Path) -> Result<Self, MembersHistoryError> {
+        let raw = match fs::read_to_string(path) {
+            Ok(raw) => raw,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(source) => {
+                return Err(MembersHistoryError::Read {
+                    path: path.to_path_buf(),
+                    source,
+                })
+            }
+        };
+
+        serde_json::from_str(&raw).map_err(|source| MembersHistoryError::Parse {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), MembersHistoryError> {
+        let raw = serde_json::to_string_pretty(self).map_err(MembersHistoryError::Serialize)?;
+
+        match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => fs::create_dir_all(dir),
+            _ => Ok(()),
+        }
+        .and_then(|()| fs::write(path, raw))
+        .map_err(|source| MembersHistoryError::Write {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+
+    /// The largest count of the chat over the kept analyses
+    pub fn peak(&self, chat_id: i64) -> Option<i32> {
+        self.chats
+            .get(&chat_id)
+            .and_then(|snapshots| snapshots.iter().map(|snapshot| snapshot.count).max())
+    }
+
+    /// Add the count of this analysis. The count of the same day replaces the previous one, so
+    /// repeated analyses don't push out the older snapshots.
+    pub fn record(&mut self, chat_id: i64, count: i32, now: DateTime<Utc>) {
+        let snapshots = self.chats.entry(chat_id).or_default();
+        let snapshot = Snapshot {
+            date: now.timestamp(),
+            count,
+        };
+
+        match snapshots.last_mut() {
+            Some(last) if last.date / 86400 == snapshot.date / 86400 => *last = snapshot,
+            _ => snapshots.push(snapshot),
+        }
+        if snapshots.len() > SNAPSHOTS_LIMIT {
+            snapshots.drain(..snapshots.len() - SNAPSHOTS_LIMIT);
+        }
+    }
+}
+
+/// Nobody but you and bots is left
+pub const fn only_you_left(members: &Members) -> bool {
+    match members.count {
+        Some(count) => count as i64 <= 1 + members.bots as i64,
+        None => false,
+    }
+}
+
+/// Nobody but bots can manage the chat
+pub const fn admins_gone(members: &Members) -> bool {
+    matches!(members.admins, Some(0))
+}
+
+/// The chat lost at least a quarter of the members since its peak
+pub const fn is_shrinking(members: &Members, peak: Option<i32>) -> bool {
+    match (members.count, peak) {
+        (Some(count), Some(peak)) => (count as i64) * 100 < (peak as i64) * KEPT_MEMBERS_PERCENT,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+
+    #[test]
+    fn peak_is_kept_over_the_last_analyses() {
+        let now = Utc::now();
+        let mut history = MembersHistory::default();
+
+        history.record(1, 100, now - Duration::days(40));
+        history.record(1, 80, now - Duration::days(20));
+        history.record(1, 60, now - Duration::days(20));
+
+        assert_eq!(history.peak(1), Some(100));
+        assert_eq!(history.chats[&1].len(), 2);
+        assert_eq!(history.peak(2), None);
+
+        for days in 0..SNAPSHOTS_LIMIT {
+            history.record(1, 50, now - Duration::days(days as i64));
+        }
+        assert_eq!(history.peak(1), Some(50));
+    }
+}
@@ -1,8 +1,9 @@
-use grammers_client::types::Message;
 use grammers_session::PackedChat;
-use grammers_tl_types::types as tl_types;
 use std::fmt::{self, Display, Formatter};
 
+use super::{activity::Activity, stats::ChatStats};
+use crate::client::api::{ChatInfo, Members, Message};
+
 #[derive(Debug)]
 pub struct MessageDisplay(pub Box<Message>);
 
@@ -16,10 +17,11 @@ impl Display for MessageDisplay {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "Msg(id={message_id}, date={date}, action={action:#?})",
-            message_id = self.0.id(),
-            date = self.0.date(),
-            action = self.0.action()
+            "Msg(id={message_id}, date={date}, kind={kind}, action={action:#?})",
+            message_id = self.0.id,
+            date = self.0.date,
+            kind = self.0.kind(),
+            action = self.0.action
         )
     }
 }
@@ -60,6 +62,8 @@ pub struct ChatLastMessageOld {
     pub name: Box<str>,
     pub username: Option<Box<str>>,
     pub message: MessageDisplay,
+    /// `None` if the chat history can't be fetched
+    pub stats: Option<ChatStats>,
 }
 
 impl Display for ChatLastMessageOld {
@@ -96,8 +100,10 @@ impl Display for ChatLastMessageOld {
 
 #[derive(Debug)]
 pub struct LeavedMessageOld {
-    pub chat: tl_types::Chat,
+    pub chat: ChatInfo,
     pub message: MessageDisplay,
+    /// `None` if the chat history can't be fetched
+    pub stats: Option<ChatStats>,
 }
 
 impl Display for LeavedMessageOld {
@@ -114,8 +120,10 @@ impl Display for LeavedMessageOld {
 
 #[derive(Debug)]
 pub struct LeavedChannelMessageOld {
-    pub channel: tl_types::Channel,
+    pub channel: ChatInfo,
     pub message: MessageDisplay,
+    /// `None` if the chat history can't be fetched
+    pub stats: Option<ChatStats>,
 }
 
 impl Display for LeavedChannelMessageOld {
@@ -154,6 +162,8 @@ pub struct LastMessagesOld {
     pub name: Box<str>,
     pub username: Option<Box<str>>,
     pub messages: MessagesDisplay,
+    /// `None` if the chat history can't be fetched
+    pub stats: Option<ChatStats>,
 }
 
 impl Display for LastMessagesOld {
@@ -190,8 +200,10 @@ impl Display for LastMessagesOld {
 
 #[derive(Debug)]
 pub struct LeavedMessagesOld {
-    pub chat: tl_types::Chat,
+    pub chat: ChatInfo,
     pub messages: MessagesDisplay,
+    /// `None` if the chat history can't be fetched
+    pub stats: Option<ChatStats>,
 }
 
 impl Display for LeavedMessagesOld {
@@ -208,8 +220,10 @@ impl Display for LeavedMessagesOld {
 
 #[derive(Debug)]
 pub struct LeavedChannelMessagesOld {
-    pub channel: tl_types::Channel,
+    pub channel: ChatInfo,
     pub messages: MessagesDisplay,
+    /// `None` if the chat history can't be fetched
+    pub stats: Option<ChatStats>,
 }
 
 impl Display for LeavedChannelMessagesOld {
@@ -242,11 +256,208 @@ impl Display for LeavedChannelMessagesOld {
     }
 }
 
+#[derive(Debug)]
+pub struct ActivityLow {
+    pub packed: PackedChat,
+    pub name: Box<str>,
+    pub username: Option<Box<str>>,
+    pub activity: Activity,
+    /// `None` if the chat history can't be fetched
+    pub stats: Option<ChatStats>,
+}
+
+impl Display for ActivityLow {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if let Some(ref username) = self.username {
+            write!(
+                f,
+                "{ty}({chat_id}, @{username}, title={name}, access_hash={access_hash})\n{activity}",
+                ty = self.packed.ty,
+                chat_id = self.packed.id,
+                name = self.name,
+                access_hash = self
+                    .packed
+                    .access_hash
+                    .map_or("unknown".to_owned(), |access_hash| access_hash.to_string()),
+                activity = self.activity,
+            )
+        } else {
+            write!(
+                f,
+                "{ty}({chat_id}, title={name}, access_hash={access_hash})\n{activity}",
+                ty = self.packed.ty,
+                chat_id = self.packed.id,
+                name = self.name,
+                access_hash = self
+                    .packed
+                    .access_hash
+                    .map_or("unknown".to_owned(), |access_hash| access_hash.to_string()),
+                activity = self.activity,
+            )
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct OwnMessageOld {
+    pub packed: PackedChat,
+    pub name: Box<str>,
+    pub username: Option<Box<str>>,
+    /// `None` if you've never written to the chat
+    pub message: Option<MessageDisplay>,
+    /// `None` if the chat history can't be fetched
+    pub stats: Option<ChatStats>,
+}
+
+impl Display for OwnMessageOld {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let message = self
+            .message
+            .as_ref()
+            .map_or("Never written".to_owned(), ToString::to_string);
+
+        if let Some(ref username) = self.username {
+            write!(
+                f,
+                "{ty}({chat_id}, @{username}, title={name}, access_hash={access_hash})\n{message}",
+                ty = self.packed.ty,
+                chat_id = self.packed.id,
+                name = self.name,
+                access_hash = self
+                    .packed
+                    .access_hash
+                    .map_or("unknown".to_owned(), |access_hash| access_hash.to_string()),
+            )
+        } else {
+            write!(
+                f,
+                "{ty}({chat_id}, title={name}, access_hash={access_hash})\n{message}",
+                ty = self.packed.ty,
+                chat_id = self.packed.id,
+                name = self.name,
+                access_hash = self
+                    .packed
+                    .access_hash
+                    .map_or("unknown".to_owned(), |access_hash| access_hash.to_string()),
+            )
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct UnreadMountain {
+    pub packed: PackedChat,
+    pub name: Box<str>,
+    pub username: Option<Box<str>>,
+    pub unread_count: i32,
+    /// `None` if you've read nothing in the chat
+    pub last_read: Option<MessageDisplay>,
+    /// `None` if the chat history can't be fetched
+    pub stats: Option<ChatStats>,
+}
+
+impl Display for UnreadMountain {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let last_read = self
+            .last_read
+            .as_ref()
+            .map_or("never".to_owned(), ToString::to_string);
+
+        if let Some(ref username) = self.username {
+            write!(
+                f,
+                "{ty}({chat_id}, @{username}, title={name}, access_hash={access_hash})\nUnread(count={unread_count}, last_read={last_read})",
+                ty = self.packed.ty,
+                chat_id = self.packed.id,
+                name = self.name,
+                access_hash = self
+                    .packed
+                    .access_hash
+                    .map_or("unknown".to_owned(), |access_hash| access_hash.to_string()),
+                unread_count = self.unread_count,
+            )
+        } else {
+            write!(
+                f,
+                "{ty}({chat_id}, title={name}, access_hash={access_hash})\nUnread(count={unread_count}, last_read={last_read})",
+                ty = self.packed.ty,
+                chat_id = self.packed.id,
+                name = self.name,
+                access_hash = self
+                    .packed
+                    .access_hash
+                    .map_or("unknown".to_owned(), |access_hash| access_hash.to_string()),
+                unread_count = self.unread_count,
+            )
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct MembersGone {
+    pub packed: PackedChat,
+    pub name: Box<str>,
+    pub username: Option<Box<str>>,
+    pub members: Members,
+    /// The largest count of the previous analyses, `None` if they aren't kept
+    pub peak: Option<i32>,
+    /// `None` if the chat history can't be fetched
+    pub stats: Option<ChatStats>,
+}
+
+impl Display for MembersGone {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let members = format!(
+            "Members(count={count}, bots={bots}, admins={admins}, peak={peak})",
+            count = self
+                .members
+                .count
+                .map_or("unknown".to_owned(), |count| count.to_string()),
+            bots = self.members.bots,
+            admins = self
+                .members
+                .admins
+                .map_or("unknown".to_owned(), |admins| admins.to_string()),
+            peak = self
+                .peak
+                .map_or("unknown".to_owned(), |peak| peak.to_string()),
+        );
+
+        if let Some(ref username) = self.username {
+            write!(
+                f,
+                "{ty}({chat_id}, @{username}, title={name}, access_hash={access_hash})\n{members}",
+                ty = self.packed.ty,
+                chat_id = self.packed.id,
+                name = self.name,
+                access_hash = self
+                    .packed
+                    .access_hash
+                    .map_or("unknown".to_owned(), |access_hash| access_hash.to_string()),
+            )
+        } else {
+            write!(
+                f,
+                "{ty}({chat_id}, title={name}, access_hash={access_hash})\n{members}",
+                ty = self.packed.ty,
+                chat_id = self.packed.id,
+                name = self.name,
+                access_hash = self
+                    .packed
+                    .access_hash
+                    .map_or("unknown".to_owned(), |access_hash| access_hash.to_string()),
+            )
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct MessagesEmpty {
     pub packed: PackedChat,
     pub name: Box<str>,
     pub username: Option<Box<str>>,
+    /// `None` if the chat history can't be fetched
+    pub stats: Option<ChatStats>,
 }
 
 impl Display for MessagesEmpty {
@@ -296,6 +507,8 @@ pub struct MessagesCountSmall {
     pub name: Box<str>,
     pub username: Option<Box<str>>,
     pub messages: MessagesDisplay,
+    /// `None` if the chat history can't be fetched
+    pub stats: Option<ChatStats>,
 }
 
 impl Display for MessagesCountSmall {
@@ -332,8 +545,10 @@ impl Display for MessagesCountSmall {
 
 #[derive(Debug)]
 pub struct LeavedMessagesCountSmall {
-    pub chat: tl_types::Chat,
+    pub chat: ChatInfo,
     pub messages: MessagesDisplay,
+    /// `None` if the chat history can't be fetched
+    pub stats: Option<ChatStats>,
 }
 
 impl Display for LeavedMessagesCountSmall {
@@ -350,8 +565,10 @@ impl Display for LeavedMessagesCountSmall {
 
 #[derive(Debug)]
 pub struct LeavedChannelMessagesCountSmall {
-    pub channel: tl_types::Channel,
+    pub channel: ChatInfo,
     pub messages: MessagesDisplay,
+    /// `None` if the chat history can't be fetched
+    pub stats: Option<ChatStats>,
 }
 
 impl Display for LeavedChannelMessagesCountSmall {
@@ -386,7 +603,9 @@ impl Display for LeavedChannelMessagesCountSmall {
 
 #[derive(Debug)]
 pub struct CreatorLeaved {
-    pub chat: tl_types::Chat,
+    pub chat: ChatInfo,
+    /// `None` if the chat history can't be fetched
+    pub stats: Option<ChatStats>,
 }
 
 impl Display for CreatorLeaved {
@@ -402,7 +621,9 @@ impl Display for CreatorLeaved {
 
 #[derive(Debug)]
 pub struct ChannelCreatorLeaved {
-    pub channel: Box<tl_types::Channel>,
+    pub channel: ChatInfo,
+    /// `None` if the chat history can't be fetched
+    pub stats: Option<ChatStats>,
 }
 
 impl Display for ChannelCreatorLeaved {
@@ -441,6 +662,12 @@ pub enum Chat {
     LastMessagesOld(LastMessagesOld),
     LeavedMessagesOld(LeavedMessagesOld),
     LeavedChannelMessagesOld(LeavedChannelMessagesOld),
+    ActivityLow(ActivityLow),
+    OwnMessageOld(OwnMessageOld),
+    UnreadMountain(UnreadMountain),
+    OnlyYouLeft(MembersGone),
+    AdminsGone(MembersGone),
+    MembersShrinking(MembersGone),
     MessagesEmpty(MessagesEmpty),
     Empty(Empty),
     MessagesCountSmall(MessagesCountSmall),
@@ -465,6 +692,30 @@ impl Chat {
                 packed: PackedChat { id, .. },
                 ..
             })
+            | Chat::ActivityLow(ActivityLow {
+                packed: PackedChat { id, .. },
+                ..
+            })
+            | Chat::OwnMessageOld(OwnMessageOld {
+                packed: PackedChat { id, .. },
+                ..
+            })
+            | Chat::UnreadMountain(UnreadMountain {
+                packed: PackedChat { id, .. },
+                ..
+            })
+            | Chat::OnlyYouLeft(MembersGone {
+                packed: PackedChat { id, .. },
+                ..
+            })
+            | Chat::AdminsGone(MembersGone {
+                packed: PackedChat { id, .. },
+                ..
+            })
+            | Chat::MembersShrinking(MembersGone {
+                packed: PackedChat { id, .. },
+                ..
+            })
             | Chat::Empty(Empty { id })
             | Chat::MessagesCountSmall(MessagesCountSmall {
                 packed: PackedChat { id, .. },
@@ -473,7 +724,7 @@ impl Chat {
             Chat::LeavedMessageOld(LeavedMessageOld { chat, .. })
             | Chat::LeavedMessagesOld(LeavedMessagesOld { chat, .. })
             | Chat::LeavedMessagesCountSmall(LeavedMessagesCountSmall { chat, .. })
-            | Chat::CreatorLeaved(CreatorLeaved { chat }) => chat.id,
+            | Chat::CreatorLeaved(CreatorLeaved { chat, .. }) => chat.id,
             Chat::LeavedChannelMessageOld(LeavedChannelMessageOld { channel, .. })
             | Chat::LeavedChannelMessagesOld(LeavedChannelMessagesOld { channel, .. })
             | Chat::LeavedChannelMessagesCountSmall(LeavedChannelMessagesCountSmall {
@@ -494,6 +745,30 @@ impl Chat {
                 packed: PackedChat { access_hash, .. },
                 ..
             })
+            | Chat::ActivityLow(ActivityLow {
+                packed: PackedChat { access_hash, .. },
+                ..
+            })
+            | Chat::OwnMessageOld(OwnMessageOld {
+                packed: PackedChat { access_hash, .. },
+                ..
+            })
+            | Chat::UnreadMountain(UnreadMountain {
+                packed: PackedChat { access_hash, .. },
+                ..
+            })
+            | Chat::OnlyYouLeft(MembersGone {
+                packed: PackedChat { access_hash, .. },
+                ..
+            })
+            | Chat::AdminsGone(MembersGone {
+                packed: PackedChat { access_hash, .. },
+                ..
+            })
+            | Chat::MembersShrinking(MembersGone {
+                packed: PackedChat { access_hash, .. },
+                ..
+            })
             | Chat::MessagesEmpty(MessagesEmpty {
                 packed: PackedChat { access_hash, .. },
                 ..
@@ -518,24 +793,231 @@ impl Chat {
     }
 }
 
+impl Chat {
+    /// Chat to make requests with. It's unknown for empty chats.
+    pub fn packed(&self) -> Option<PackedChat> {
+        match self {
+            Chat::LastMessageOld(ChatLastMessageOld { packed, .. })
+            | Chat::LastMessagesOld(LastMessagesOld { packed, .. })
+            | Chat::ActivityLow(ActivityLow { packed, .. })
+            | Chat::OwnMessageOld(OwnMessageOld { packed, .. })
+            | Chat::UnreadMountain(UnreadMountain { packed, .. })
+            | Chat::OnlyYouLeft(MembersGone { packed, .. })
+            | Chat::AdminsGone(MembersGone { packed, .. })
+            | Chat::MembersShrinking(MembersGone { packed, .. })
+            | Chat::MessagesEmpty(MessagesEmpty { packed, .. })
+            | Chat::MessagesCountSmall(MessagesCountSmall { packed, .. }) => Some(*packed),
+            Chat::LeavedMessageOld(LeavedMessageOld { chat, .. })
+            | Chat::LeavedMessagesOld(LeavedMessagesOld { chat, .. })
+            | Chat::LeavedMessagesCountSmall(LeavedMessagesCountSmall { chat, .. })
+            | Chat::CreatorLeaved(CreatorLeaved { chat, .. }) => Some(chat.packed()),
+            Chat::LeavedChannelMessageOld(LeavedChannelMessageOld { channel, .. })
+            | Chat::LeavedChannelMessagesOld(LeavedChannelMessagesOld { channel, .. })
+            | Chat::LeavedChannelMessagesCountSmall(LeavedChannelMessagesCountSmall {
+                channel,
+                ..
+            })
+            | Chat::ChannelCreatorLeaved(ChannelCreatorLeaved { channel, .. }) => {
+                Some(channel.packed())
+            }
+            Chat::Empty(_) => None,
+        }
+    }
+
+    pub fn title(&self) -> Option<&str> {
+        match self {
+            Chat::LastMessageOld(ChatLastMessageOld { name, .. })
+            | Chat::LastMessagesOld(LastMessagesOld { name, .. })
+            | Chat::ActivityLow(ActivityLow { name, .. })
+            | Chat::OwnMessageOld(OwnMessageOld { name, .. })
+            | Chat::UnreadMountain(UnreadMountain { name, .. })
+            | Chat::OnlyYouLeft(MembersGone { name, .. })
+            | Chat::AdminsGone(MembersGone { name, .. })
+            | Chat::MembersShrinking(MembersGone { name, .. })
+            | Chat::MessagesEmpty(MessagesEmpty { name, .. })
+            | Chat::MessagesCountSmall(MessagesCountSmall { name, .. }) => Some(name),
+            Chat::LeavedMessageOld(LeavedMessageOld { chat, .. })
+            | Chat::LeavedMessagesOld(LeavedMessagesOld { chat, .. })
+            | Chat::LeavedMessagesCountSmall(LeavedMessagesCountSmall { chat, .. })
+            | Chat::CreatorLeaved(CreatorLeaved { chat, .. }) => Some(&chat.title),
+            Chat::LeavedChannelMessageOld(LeavedChannelMessageOld { channel, .. })
+            | Chat::LeavedChannelMessagesOld(LeavedChannelMessagesOld { channel, .. })
+            | Chat::LeavedChannelMessagesCountSmall(LeavedChannelMessagesCountSmall {
+                channel,
+                ..
+            }) => Some(&channel.title),
+            Chat::ChannelCreatorLeaved(ChannelCreatorLeaved { channel, .. }) => {
+                Some(&channel.title)
+            }
+            Chat::Empty(_) => None,
+        }
+    }
+
+    /// Whether you aren't a member of the chat anymore
+    pub const fn is_left(&self) -> bool {
+        match self {
+            Chat::LastMessageOld(_)
+            | Chat::LastMessagesOld(_)
+            | Chat::ActivityLow(_)
+            | Chat::OwnMessageOld(_)
+            | Chat::UnreadMountain(_)
+            | Chat::OnlyYouLeft(_)
+            | Chat::AdminsGone(_)
+            | Chat::MembersShrinking(_)
+            | Chat::MessagesEmpty(_)
+            | Chat::MessagesCountSmall(_) => false,
+            Chat::LeavedMessageOld(_)
+            | Chat::LeavedChannelMessageOld(_)
+            | Chat::LeavedMessagesOld(_)
+            | Chat::LeavedChannelMessagesOld(_)
+            | Chat::Empty(_)
+            | Chat::LeavedMessagesCountSmall(_)
+            | Chat::LeavedChannelMessagesCountSmall(_)
+            | Chat::CreatorLeaved(_)
+            | Chat::ChannelCreatorLeaved(_) => true,
+        }
+    }
+
+    /// Messages the chat is found by, the latest first
+    pub fn messages(&self) -> &[MessageDisplay] {
+        match self {
+            Chat::LastMessageOld(ChatLastMessageOld { message, .. })
+            | Chat::LeavedMessageOld(LeavedMessageOld { message, .. })
+            | Chat::LeavedChannelMessageOld(LeavedChannelMessageOld { message, .. }) => {
+                std::slice::from_ref(message)
+            }
+            Chat::OwnMessageOld(OwnMessageOld { message, .. })
+            | Chat::UnreadMountain(UnreadMountain {
+                last_read: message, ..
+            }) => message.as_ref().map_or(&[], std::slice::from_ref),
+            Chat::LastMessagesOld(LastMessagesOld { messages, .. })
+            | Chat::LeavedMessagesOld(LeavedMessagesOld { messages, .. })
+            | Chat::LeavedChannelMessagesOld(LeavedChannelMessagesOld { messages, .. })
+            | Chat::MessagesCountSmall(MessagesCountSmall { messages, .. })
+            | Chat::LeavedMessagesCountSmall(LeavedMessagesCountSmall { messages, .. })
+            | Chat::LeavedChannelMessagesCountSmall(LeavedChannelMessagesCountSmall {
+                messages,
+                ..
+            }) => &messages.0,
+            Chat::ActivityLow(_)
+            | Chat::OnlyYouLeft(_)
+            | Chat::AdminsGone(_)
+            | Chat::MembersShrinking(_)
+            | Chat::MessagesEmpty(_)
+            | Chat::Empty(_)
+            | Chat::CreatorLeaved(_)
+            | Chat::ChannelCreatorLeaved(_) => &[],
+        }
+    }
+
+    /// Numbers about the chat history, `None` for empty chats
+    pub const fn stats(&self) -> Option<&ChatStats> {
+        match self {
+            Chat::LastMessageOld(ChatLastMessageOld { stats, .. })
+            | Chat::LeavedMessageOld(LeavedMessageOld { stats, .. })
+            | Chat::LeavedChannelMessageOld(LeavedChannelMessageOld { stats, .. })
+            | Chat::LastMessagesOld(LastMessagesOld { stats, .. })
+            | Chat::LeavedMessagesOld(LeavedMessagesOld { stats, .. })
+            | Chat::LeavedChannelMessagesOld(LeavedChannelMessagesOld { stats, .. })
+            | Chat::ActivityLow(ActivityLow { stats, .. })
+            | Chat::OwnMessageOld(OwnMessageOld { stats, .. })
+            | Chat::UnreadMountain(UnreadMountain { stats, .. })
+            | Chat::OnlyYouLeft(MembersGone { stats, .. })
+            | Chat::AdminsGone(MembersGone { stats, .. })
+            | Chat::MembersShrinking(MembersGone { stats, .. })
+            | Chat::MessagesEmpty(MessagesEmpty { stats, .. })
+            | Chat::MessagesCountSmall(MessagesCountSmall { stats, .. })
+            | Chat::LeavedMessagesCountSmall(LeavedMessagesCountSmall { stats, .. })
+            | Chat::LeavedChannelMessagesCountSmall(LeavedChannelMessagesCountSmall {
+                stats,
+                ..
+            })
+            | Chat::CreatorLeaved(CreatorLeaved { stats, .. })
+            | Chat::ChannelCreatorLeaved(ChannelCreatorLeaved { stats, .. }) => stats.as_ref(),
+            Chat::Empty(_) => None,
+        }
+    }
+
+    /// Messages per month the chat is found by
+    pub const fn activity(&self) -> Option<&Activity> {
+        match self {
+            Chat::ActivityLow(ActivityLow { activity, .. }) => Some(activity),
+            _ => None,
+        }
+    }
+
+    /// Members the chat is found by
+    pub const fn members(&self) -> Option<&Members> {
+        match self {
+            Chat::OnlyYouLeft(MembersGone { members, .. })
+            | Chat::AdminsGone(MembersGone { members, .. })
+            | Chat::MembersShrinking(MembersGone { members, .. }) => Some(members),
+            _ => None,
+        }
+    }
+
+    /// Unread messages the chat is found by
+    pub const fn unread_count(&self) -> Option<i32> {
+        match self {
+            Chat::UnreadMountain(UnreadMountain { unread_count, .. }) => Some(*unread_count),
+            _ => None,
+        }
+    }
+
+    pub const fn reason(&self) -> &'static str {
+        match self {
+            Chat::LastMessageOld(_)
+            | Chat::LeavedMessageOld(_)
+            | Chat::LeavedChannelMessageOld(_) => "Last message too old",
+            Chat::LastMessagesOld(_)
+            | Chat::LeavedMessagesOld(_)
+            | Chat::LeavedChannelMessagesOld(_) => "Last messages too old",
+            Chat::ActivityLow(_) => "Activity too low",
+            Chat::OwnMessageOld(_) => "Your last message too old",
+            Chat::UnreadMountain(_) => "Not followed anymore",
+            Chat::OnlyYouLeft(_) => "Only you and bots left",
+            Chat::AdminsGone(_) => "Admins gone",
+            Chat::MembersShrinking(_) => "Members leaving",
+            Chat::MessagesEmpty(_) => "Messages empty",
+            Chat::Empty(_) => "Empty",
+            Chat::MessagesCountSmall(_)
+            | Chat::LeavedMessagesCountSmall(_)
+            | Chat::LeavedChannelMessagesCountSmall(_) => "Messages count too small",
+            Chat::CreatorLeaved(_) | Chat::ChannelCreatorLeaved(_) => "Leaved as creator",
+        }
+    }
+}
+
 impl Display for Chat {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
-            Chat::LastMessageOld(val) => write!(f, "Last message too old: {val}"),
-            Chat::LeavedMessageOld(val) => write!(f, "Last message too old: {val}"),
-            Chat::LeavedChannelMessageOld(val) => write!(f, "Last message too old: {val}"),
-            Chat::LastMessagesOld(val) => write!(f, "Last messages too old: {val}"),
-            Chat::LeavedMessagesOld(val) => write!(f, "Last messages too old: {val}"),
-            Chat::LeavedChannelMessagesOld(val) => write!(f, "Last messages too old: {val}"),
-            Chat::MessagesEmpty(val) => write!(f, "Messages empty: {val}"),
-            Chat::Empty(val) => write!(f, "{val}"),
-            Chat::MessagesCountSmall(val) => write!(f, "Messages count too small: {val}"),
-            Chat::LeavedMessagesCountSmall(val) => write!(f, "Messages count too small: {val}"),
+            Chat::LastMessageOld(val) => write!(f, "Last message too old: {val}")?,
+            Chat::LeavedMessageOld(val) => write!(f, "Last message too old: {val}")?,
+            Chat::LeavedChannelMessageOld(val) => write!(f, "Last message too old: {val}")?,
+            Chat::LastMessagesOld(val) => write!(f, "Last messages too old: {val}")?,
+            Chat::LeavedMessagesOld(val) => write!(f, "Last messages too old: {val}")?,
+            Chat::LeavedChannelMessagesOld(val) => write!(f, "Last messages too old: {val}")?,
+            Chat::ActivityLow(val) => write!(f, "Activity too low: {val}")?,
+            Chat::OwnMessageOld(val) => write!(f, "Your last message too old: {val}")?,
+            Chat::UnreadMountain(val) => write!(f, "Not followed anymore: {val}")?,
+            Chat::OnlyYouLeft(val) => write!(f, "Only you and bots left: {val}")?,
+            Chat::AdminsGone(val) => write!(f, "Admins gone: {val}")?,
+            Chat::MembersShrinking(val) => write!(f, "Members leaving: {val}")?,
+            Chat::MessagesEmpty(val) => write!(f, "Messages empty: {val}")?,
+            Chat::Empty(val) => write!(f, "{val}")?,
+            Chat::MessagesCountSmall(val) => write!(f, "Messages count too small: {val}")?,
+            Chat::LeavedMessagesCountSmall(val) => write!(f, "Messages count too small: {val}")?,
             Chat::LeavedChannelMessagesCountSmall(val) => {
-                write!(f, "Messages count too small: {val}")
+                write!(f, "Messages count too small: {val}")?;
             }
-            Chat::CreatorLeaved(val) => write!(f, "Leaved as creator: {val}"),
-            Chat::ChannelCreatorLeaved(val) => write!(f, "Leaved as creator: {val}"),
+            Chat::CreatorLeaved(val) => write!(f, "Leaved as creator: {val}")?,
+            Chat::ChannelCreatorLeaved(val) => write!(f, "Leaved as creator: {val}")?,
         }
+
+        if let Some(stats) = self.stats() {
+            write!(f, "\n{stats}")?;
+        }
+
+        Ok(())
     }
 }
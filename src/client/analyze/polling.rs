@@ -1,19 +1,30 @@
-use chrono::{NaiveTime, Utc};
-use grammers_client::{
-    client::bots::InvocationError,
-    types::{self, Dialog, Message},
-    Client,
-};
+use chrono::{DateTime, Utc};
+use grammers_client::client::bots::InvocationError;
 use grammers_mtsender::RpcError;
 use grammers_session::{PackedChat, PackedType};
-use grammers_tl_types::{self as tl, enums, types as tl_types};
-use tracing::{debug, error, instrument, span, trace, Level};
-
-use super::models::{
-    ChannelCreatorLeaved, Chat, ChatLastMessageOld, CreatorLeaved, Empty, LastMessagesOld,
-    LeavedChannelMessageOld, LeavedChannelMessagesCountSmall, LeavedChannelMessagesOld,
-    LeavedMessageOld, LeavedMessagesCountSmall, LeavedMessagesOld, MessagesCountSmall,
-    MessagesEmpty,
+use tracing::{debug, error, instrument, span, trace, Level, Span};
+
+use crate::{
+    client::{
+        api::{ChatInfo, History, LeftChat, Members, Message, TelegramApi},
+        folders::ARCHIVE_FOLDER_ID,
+    },
+    configs::IgnoreMatcher,
+};
+
+use super::{
+    activity::{sample_activity, Activity},
+    filter::ChatFilter,
+    members::{admins_gone, is_shrinking, only_you_left},
+    models::{
+        ActivityLow, ChannelCreatorLeaved, Chat, ChatLastMessageOld, CreatorLeaved, Empty,
+        LastMessagesOld, LeavedChannelMessageOld, LeavedChannelMessagesCountSmall,
+        LeavedChannelMessagesOld, LeavedMessageOld, LeavedMessagesCountSmall, LeavedMessagesOld,
+        MembersGone, MessagesCountSmall, MessagesDisplay, MessagesEmpty, OwnMessageOld,
+        UnreadMountain,
+    },
+    rules::Rules,
+    stats::{fetch_stats, ChatStats},
 };
 
 const OLD_MESSAGE_ELAPSED_DAYS: i64 = 30;
@@ -29,399 +40,1084 @@ const fn messages_count_is_too_small(messages_count: usize) -> bool {
 
 fn last_message_is_old(
     message: &Message,
-    now_time: NaiveTime,
+    now: DateTime<Utc>,
     old_message_elapsed_days: i64,
 ) -> bool {
-    let last_message_time = message.date().time();
-    let elapsed = now_time - last_message_time;
+    let elapsed = now - message.date;
 
     elapsed.num_days() > old_message_elapsed_days
 }
 
+/// Messages are the latest first
 fn old_messages_count_limit_reached(
     messages: &[Message],
     elapsed_days_between_old_messages: i64,
 ) -> bool {
-    let mut rev_messages = messages.to_vec();
-    rev_messages.reverse();
-
-    let mut old_messages_count = 0;
-    let mut last_message = rev_messages.remove(0);
-    let mut last_message_time = last_message.date().time();
-    for message in rev_messages {
-        let message_time = message.date().time();
-        let elapsed = last_message_time - message_time;
-
-        if elapsed.num_days() >= elapsed_days_between_old_messages {
-            old_messages_count += 1;
-        }
+    let old_messages_count = messages
+        .windows(2)
+        .filter(|pair| {
+            let elapsed = pair[0].date - pair[1].date;
 
-        last_message = message;
-        last_message_time = message_time;
-    }
-    drop(last_message);
+            elapsed.num_days() >= elapsed_days_between_old_messages
+        })
+        .count();
 
     old_messages_count >= OLD_MESSAGES_COUNT
 }
 
-#[instrument(skip_all)]
-pub async fn get_chats(client: &Client) -> Result<Vec<Chat>, InvocationError> {
-    let mut chats = vec![];
-    let mut dialogs = client.iter_dialogs();
-    let mut dialogs_count = 0;
+/// Messages sent by people, the activity is judged by. Service messages, bot messages and
+/// forwards are skipped, so joins, pins and bot spam don't make a chat look alive.
+fn human_messages(messages: &[Message]) -> Vec<Message> {
+    messages
+        .iter()
+        .filter(|message| message.is_human())
+        .cloned()
+        .collect()
+}
 
-    let now_time = Utc::now().time();
+/// Last messages of the chat, `None` if they can't be fetched
+async fn fetch_messages(
+    client: &impl TelegramApi,
+    chat: PackedChat,
+    span: &Span,
+) -> Option<History> {
+    match client.get_messages(chat, LAST_MESSAGES_LIMIT).await {
+        Ok(history) => Some(history),
+        Err(InvocationError::Rpc(RpcError {
+            code: _code @ 400, ..
+        })) => {
+            debug!(parent: span, "Chat is private");
 
-    'outer: while let Some(Dialog {
-        chat, last_message, ..
-    }) = dialogs.next().await?
-    {
-        dialogs_count += 1;
+            None
+        }
+        Err(err) => {
+            error!(parent: span, %err, "Error while get chat messages");
 
-        let chat_id = chat.id();
+            None
+        }
+    }
+}
 
-        let span = span!(Level::DEBUG, "iter", chat_id, num = dialogs_count);
-        let _guard = span.enter();
+/// Stats of the found chat, `None` if they can't be fetched
+async fn chat_stats(
+    client: &impl TelegramApi,
+    chat: PackedChat,
+    history: Option<&History>,
+    now: DateTime<Utc>,
+    span: &Span,
+) -> Option<ChatStats> {
+    match fetch_stats(client, chat, history, LAST_MESSAGES_LIMIT, now).await {
+        Ok(stats) => Some(stats),
+        Err(InvocationError::Rpc(RpcError { ref name, .. })) if name == "CHANNEL_PRIVATE" => {
+            debug!(parent: span, "Chat is private, its stats are unknown");
 
-        let mut old_message_elapsed_days = OLD_MESSAGE_ELAPSED_DAYS;
-        let mut elapsed_days_between_old_messages = ELAPSED_DAYS_BETWEEN_OLD_MESSAGES;
-        match chat {
-            types::Chat::User(_) => continue,
-            types::Chat::Group(_) => {}
-            types::Chat::Channel(ref channel) => {
-                match channel.pack().ty {
-                    PackedType::Megagroup | PackedType::Broadcast | PackedType::Gigagroup => {
-                        old_message_elapsed_days *= CHANNEL_ELAPSED_MULTIPLIER;
-                        elapsed_days_between_old_messages *= CHANNEL_ELAPSED_MULTIPLIER;
-                    }
-                    _ => {}
-                };
-            }
-        };
+            None
+        }
+        Err(err) => {
+            error!(parent: span, %err, "Error while get chat stats");
 
-        if let Some(last_message) = last_message {
-            if last_message_is_old(&last_message, now_time, old_message_elapsed_days) {
-                debug!(parent: &span, "Found an old chat by last message");
+            None
+        }
+    }
+}
 
-                chats.push(Chat::LastMessageOld(ChatLastMessageOld {
-                    username: chat.username().map(Into::into),
-                    name: chat.name().into(),
-                    packed: chat.into(),
-                    message: last_message.into(),
-                }));
-                continue;
-            }
-        } else {
-            debug!(parent: &span, "Last message not found");
+fn messages_display(messages: Vec<Message>) -> MessagesDisplay {
+    messages
+        .into_iter()
+        .map(Into::into)
+        .collect::<Vec<_>>()
+        .into()
+}
 
-            chats.push(Chat::MessagesEmpty(MessagesEmpty {
-                username: chat.username().map(Into::into),
-                name: chat.name().into(),
-                packed: chat.into(),
-            }));
-            continue;
+/// Joined chat with the thresholds of its kind
+struct Candidate {
+    packed: PackedChat,
+    name: Box<str>,
+    username: Option<Box<str>>,
+    old_message_elapsed_days: i64,
+    elapsed_days_between_old_messages: i64,
+    multiplier: i64,
+}
+
+impl Candidate {
+    fn new(chat: ChatInfo) -> Self {
+        // Supergroups and channels are bigger, so they're quiet longer than basic groups
+        let multiplier = if chat.ty != PackedType::Chat {
+            CHANNEL_ELAPSED_MULTIPLIER
+        } else {
+            1
         };
 
-        let mut messages_iter = client.iter_messages(&chat).limit(LAST_MESSAGES_LIMIT);
-        let mut messages = Vec::with_capacity(LAST_MESSAGES_LIMIT);
-        while let Some(message) = match messages_iter.next().await {
-            Ok(message) => message,
-            Err(err) => {
-                match err {
-                    InvocationError::Rpc(RpcError {
-                        code: _code @ 400, ..
-                    }) => {
-                        debug!(parent: &span, "Chat is private");
-                    }
-                    _ => {
-                        error!(parent: &span, %err, "Error while get chat messages");
-                    }
-                };
-
-                continue 'outer;
-            }
-        } {
-            messages.push(message);
+        Candidate {
+            packed: chat.packed(),
+            name: chat.title,
+            username: chat.username,
+            old_message_elapsed_days: OLD_MESSAGE_ELAPSED_DAYS * multiplier,
+            elapsed_days_between_old_messages: ELAPSED_DAYS_BETWEEN_OLD_MESSAGES * multiplier,
+            multiplier,
         }
+    }
 
-        if messages_count_is_too_small(messages.len()) {
-            debug!(parent: &span, "Messages count in the chat is too small");
-
-            chats.push(Chat::MessagesCountSmall(MessagesCountSmall {
-                username: chat.username().map(Into::into),
-                name: chat.name().into(),
-                packed: chat.into(),
-                messages: messages
-                    .into_iter()
-                    .map(Into::into)
-                    .collect::<Vec<_>>()
-                    .into(),
-            }));
-        } else if old_messages_count_limit_reached(&messages, elapsed_days_between_old_messages) {
-            debug!(parent: &span, "Found an old chat by last messages which are periodically sent with high delay");
+    fn empty(self, stats: Option<ChatStats>) -> Chat {
+        Chat::MessagesEmpty(MessagesEmpty {
+            packed: self.packed,
+            name: self.name,
+            username: self.username,
+            stats,
+        })
+    }
 
-            chats.push(Chat::LastMessagesOld(LastMessagesOld {
-                username: chat.username().map(Into::into),
-                name: chat.name().into(),
-                packed: chat.into(),
-                messages: messages
-                    .into_iter()
-                    .map(Into::into)
-                    .collect::<Vec<_>>()
-                    .into(),
-            }));
-        }
+    fn last_message_old(self, message: Message, stats: Option<ChatStats>) -> Chat {
+        Chat::LastMessageOld(ChatLastMessageOld {
+            packed: self.packed,
+            name: self.name,
+            username: self.username,
+            message: message.into(),
+            stats,
+        })
     }
 
-    Ok(chats)
-}
+    fn messages_count_small(self, messages: Vec<Message>, stats: Option<ChatStats>) -> Chat {
+        Chat::MessagesCountSmall(MessagesCountSmall {
+            username: self.username,
+            name: self.name,
+            packed: self.packed,
+            messages: messages_display(messages),
+            stats,
+        })
+    }
 
-#[instrument(skip_all)]
-pub async fn init_takeout_session(client: &Client) -> Result<i64, InvocationError> {
-    client
-        .invoke(&tl::functions::account::InitTakeoutSession {
-            contacts: false,
-            message_users: false,
-            message_chats: true,
-            message_megagroups: true,
-            message_channels: true,
-            files: false,
-            file_max_size: None,
+    fn last_messages_old(self, messages: Vec<Message>, stats: Option<ChatStats>) -> Chat {
+        Chat::LastMessagesOld(LastMessagesOld {
+            username: self.username,
+            name: self.name,
+            packed: self.packed,
+            messages: messages_display(messages),
+            stats,
         })
-        .await
-        .map(|enums::account::Takeout::Takeout(tl_types::account::Takeout { id })| id)
-}
+    }
 
-#[instrument(skip_all, fields(takeout_id, success))]
-pub async fn finish_takeout_session(
-    client: &Client,
-    takeout_id: i64,
-    success: bool,
-) -> Result<bool, InvocationError> {
-    client
-        .invoke(&tl::functions::InvokeWithTakeout {
-            takeout_id,
-            query: tl::functions::account::FinishTakeoutSession { success },
+    fn own_message_old(self, message: Option<Message>, stats: Option<ChatStats>) -> Chat {
+        Chat::OwnMessageOld(OwnMessageOld {
+            packed: self.packed,
+            name: self.name,
+            username: self.username,
+            message: message.map(Into::into),
+            stats,
         })
-        .await
+    }
+
+    fn unread_mountain(
+        self,
+        unread_count: i32,
+        last_read: Option<Message>,
+        stats: Option<ChatStats>,
+    ) -> Chat {
+        Chat::UnreadMountain(UnreadMountain {
+            packed: self.packed,
+            name: self.name,
+            username: self.username,
+            unread_count,
+            last_read: last_read.map(Into::into),
+            stats,
+        })
+    }
+
+    /// `found` is the variant of the rule the chat is found by
+    fn members_gone(
+        self,
+        found: fn(MembersGone) -> Chat,
+        members: Members,
+        peak: Option<i32>,
+        stats: Option<ChatStats>,
+    ) -> Chat {
+        found(MembersGone {
+            packed: self.packed,
+            name: self.name,
+            username: self.username,
+            members,
+            peak,
+            stats,
+        })
+    }
+
+    fn activity_low(self, activity: Activity, stats: Option<ChatStats>) -> Chat {
+        Chat::ActivityLow(ActivityLow {
+            username: self.username,
+            name: self.name,
+            packed: self.packed,
+            activity,
+            stats,
+        })
+    }
 }
 
+/// Joined chats from the main list and the archive, as the filter allows
 #[instrument(skip_all)]
-pub async fn get_left_chats(
-    client: &Client,
-    takeout_id: i64,
+pub async fn get_chats(
+    client: &impl TelegramApi,
+    ignored: &IgnoreMatcher,
+    filter: &ChatFilter,
+    rules: &Rules,
 ) -> Result<Vec<Chat>, InvocationError> {
+    let mut dialogs = vec![];
+    if filter.includes_main_list() {
+        dialogs.extend(client.get_dialogs().await?);
+    }
+    if filter.includes_archive() {
+        dialogs.extend(client.get_folder_dialogs(ARCHIVE_FOLDER_ID).await?);
+    }
+
     let mut chats = vec![];
-    let mut chats_count = 0;
 
-    let now_time = Utc::now().time();
+    let now = client.now();
 
-    let left_chats = match client
-        .invoke(&tl::functions::InvokeWithTakeout {
-            takeout_id,
-            query: tl::functions::channels::GetLeftChannels { offset: 0 },
-        })
-        .await?
-    {
-        enums::messages::Chats::Chats(tl_types::messages::Chats { chats })
-        | enums::messages::Chats::Slice(tl_types::messages::ChatsSlice { chats, .. }) => chats,
-    };
+    for (num, dialog) in dialogs.into_iter().enumerate() {
+        let chat_id = dialog.chat.id;
 
-    'outer: for chat in left_chats {
-        chats_count += 1;
+        let span = span!(Level::DEBUG, "iter", chat_id, num = num + 1);
+        let _guard = span.enter();
 
-        let chat_id = chat.id();
+        if ignored.is_ignored(
+            chat_id,
+            dialog.chat.username.as_deref(),
+            Some(&dialog.chat.title),
+        ) {
+            trace!(parent: &span, "Chat is ignored");
+            continue;
+        }
+        if !filter.matches_dialog(&dialog) {
+            trace!(parent: &span, "Chat is filtered out");
+            continue;
+        }
 
-        let span = span!(Level::DEBUG, "iter", chat_id, num = chats_count);
-        let _guard = span.enter();
+        let candidate = Candidate::new(dialog.chat);
+        let packed = candidate.packed;
 
-        match chat {
-            enums::Chat::Empty(_) => {
-                debug!(parent: &span, "Found an old chat by empty chat");
+        let Some(last_message) = dialog.last_message else {
+            debug!(parent: &span, "Last message not found");
 
-                chats.push(Chat::Empty(Empty { id: chat_id }));
-                continue;
-            }
-            enums::Chat::Chat(chat) => {
-                if chat.creator {
-                    debug!(parent: &span, "Found an old chat in which you're the creator");
+            let stats = chat_stats(client, packed, None, now, &span).await;
+            chats.push(candidate.empty(stats));
+            continue;
+        };
+        if last_message.is_human()
+            && last_message_is_old(&last_message, now, candidate.old_message_elapsed_days)
+        {
+            debug!(parent: &span, "Found an old chat by last message");
+
+            let stats = chat_stats(client, packed, None, now, &span).await;
+            chats.push(candidate.last_message_old(last_message, stats));
+            continue;
+        }
+
+        if rules
+            .min_unread
+            .is_some_and(|min_unread| dialog.unread_count >= min_unread)
+            && dialog.read_inbox_max_id < last_message.id
+        {
+            let last_read = if dialog.read_inbox_max_id == 0 {
+                Ok(None)
+            } else {
+                client
+                    .get_message_up_to(packed, dialog.read_inbox_max_id)
+                    .await
+            };
 
-                    chats.push(Chat::CreatorLeaved(CreatorLeaved { chat }));
+            match last_read {
+                Ok(last_read)
+                    if last_read.as_ref().is_none_or(|message| {
+                        last_message_is_old(message, now, candidate.old_message_elapsed_days)
+                    }) =>
+                {
+                    debug!(parent: &span, "Found a chat with unread messages you haven't read for a long time");
+
+                    let stats = chat_stats(client, packed, None, now, &span).await;
+                    chats.push(candidate.unread_mountain(dialog.unread_count, last_read, stats));
                     continue;
                 }
+                Ok(_) => {}
+                Err(err) => error!(parent: &span, %err, "Error while get last read message"),
+            }
+        }
 
-                let mut messages_iter = client
-                    .iter_messages(PackedChat {
-                        ty: PackedType::Chat,
-                        id: chat_id,
-                        access_hash: None,
-                    })
-                    .limit(LAST_MESSAGES_LIMIT);
-                let mut messages = Vec::with_capacity(LAST_MESSAGES_LIMIT);
-
-                while let Some(message) = match messages_iter.next().await {
-                    Ok(message) => message,
-                    Err(err) => {
-                        match err {
-                            InvocationError::Rpc(RpcError {
-                                code: _code @ 400, ..
-                            }) => {
-                                debug!(parent: &span, "Group is private");
-                            }
-                            _ => {
-                                error!(parent: &span, %err, "Error while get group messages");
-                            }
-                        };
-
-                        continue 'outer;
-                    }
-                } {
-                    messages.push(message);
-                }
+        let Some(history) = fetch_messages(client, packed, &span).await else {
+            continue;
+        };
+
+        let human = human_messages(&history.messages);
+        trace!(
+            parent: &span,
+            human = human.len(),
+            sampled = history.messages.len(),
+            "Human messages are counted",
+        );
+
+        if let Some(latest) = human.first().filter(|latest| {
+            !last_message.is_human()
+                && last_message_is_old(latest, now, candidate.old_message_elapsed_days)
+        }) {
+            debug!(parent: &span, "Found an old chat by last human message");
+
+            let stats = chat_stats(client, packed, Some(&history), now, &span).await;
+            chats.push(candidate.last_message_old(latest.clone(), stats));
+            continue;
+        }
+        // All the sampled messages are shown, so it's seen what the chat is filled with instead
+        if messages_count_is_too_small(human.len()) {
+            debug!(parent: &span, "Human messages count in the chat is too small");
 
-                if messages_count_is_too_small(messages.len()) {
-                    debug!(parent: &span, "Messages count in the leaved group is too small");
-
-                    chats.push(Chat::LeavedMessagesCountSmall(LeavedMessagesCountSmall {
-                        chat,
-                        messages: messages
-                            .into_iter()
-                            .map(Into::into)
-                            .collect::<Vec<_>>()
-                            .into(),
-                    }));
+            let stats = chat_stats(client, packed, Some(&history), now, &span).await;
+            chats.push(candidate.messages_count_small(history.messages, stats));
+            continue;
+        }
+        if old_messages_count_limit_reached(&human, candidate.elapsed_days_between_old_messages) {
+            debug!(parent: &span, "Found an old chat by last messages which are periodically sent with high delay");
+
+            let stats = chat_stats(client, packed, Some(&history), now, &span).await;
+            chats.push(candidate.last_messages_old(human, stats));
+            continue;
+        }
+
+        if let Some(months) = rules.activity_months {
+            let activity = match sample_activity(
+                client,
+                packed,
+                &history.messages,
+                LAST_MESSAGES_LIMIT,
+                months,
+                now,
+            )
+            .await
+            {
+                Ok(activity) => activity,
+                Err(err) => {
+                    error!(parent: &span, %err, "Error while sample chat messages");
                     continue;
                 }
+            };
+            trace!(parent: &span, %activity, "Activity is sampled");
 
-                let last_message = messages.remove(0);
-                if last_message_is_old(
-                    &last_message,
-                    now_time,
-                    OLD_MESSAGE_ELAPSED_DAYS * CHANNEL_ELAPSED_MULTIPLIER,
-                ) {
-                    debug!(parent: &span, "Found an old leaved group by last message");
-
-                    chats.push(Chat::LeavedMessageOld(LeavedMessageOld {
-                        chat,
-                        message: last_message.into(),
-                    }));
-                } else if old_messages_count_limit_reached(
-                    &messages,
-                    ELAPSED_DAYS_BETWEEN_OLD_MESSAGES * CHANNEL_ELAPSED_MULTIPLIER,
-                ) {
-                    debug!(parent: &span, "Found an old leaved group by last messages which are periodically sent with high delay");
-
-                    chats.push(Chat::LeavedMessagesOld(LeavedMessagesOld {
-                        chat,
-                        messages: messages
-                            .into_iter()
-                            .map(Into::into)
-                            .collect::<Vec<_>>()
-                            .into(),
-                    }));
-                }
+            if activity.is_low(candidate.multiplier) {
+                debug!(parent: &span, "Found an old chat by months without messages");
+
+                let stats = chat_stats(client, packed, Some(&history), now, &span).await;
+                chats.push(candidate.activity_low(activity, stats));
+                continue;
             }
-            enums::Chat::Channel(channel) => {
-                if channel.creator {
-                    debug!(parent: &span, "Found an old channel in which you're the creator");
+        }
 
-                    chats.push(Chat::ChannelCreatorLeaved(ChannelCreatorLeaved {
-                        channel: Box::new(channel),
-                    }));
+        // Only admins post to channels, so your messages are searched only in groups
+        if let Some(days) = rules
+            .own_message_days
+            .filter(|_| packed.ty != PackedType::Broadcast)
+        {
+            let message = match client.get_own_last_message(packed).await {
+                Ok(message) => message,
+                Err(err) => {
+                    error!(parent: &span, %err, "Error while search own messages");
                     continue;
                 }
+            };
 
-                let ty = if channel.megagroup {
-                    PackedType::Megagroup
-                } else if channel.gigagroup {
-                    PackedType::Gigagroup
-                } else if channel.broadcast {
-                    PackedType::Broadcast
-                } else {
-                    unreachable!("Found incorrect packed type")
-                };
-
-                let mut messages_iter = client
-                    .iter_messages(PackedChat {
-                        ty,
-                        id: channel.id,
-                        access_hash: channel.access_hash,
-                    })
-                    .limit(LAST_MESSAGES_LIMIT);
-                let mut messages = Vec::with_capacity(LAST_MESSAGES_LIMIT);
-
-                while let Some(message) = match messages_iter.next().await {
-                    Ok(message) => message,
-                    Err(err) => {
-                        match err {
-                            InvocationError::Rpc(RpcError {
-                                code: _code @ 400, ..
-                            }) => {
-                                debug!(parent: &span, "Channel is private");
-                            }
-                            _ => {
-                                error!(parent: &span, %err, "Error while get channel messages");
-                            }
-                        };
-
-                        continue 'outer;
-                    }
-                } {
-                    messages.push(message);
-                }
+            if message
+                .as_ref()
+                .is_none_or(|message| last_message_is_old(message, now, days))
+            {
+                debug!(parent: &span, "Found a chat you haven't written to for a long time");
+
+                let stats = chat_stats(client, packed, Some(&history), now, &span).await;
+                chats.push(candidate.own_message_old(message, stats));
+                continue;
+            }
+        }
 
-                if messages_count_is_too_small(messages.len()) {
-                    debug!(parent: &span, "Messages count in the leaved channel is too small");
-
-                    chats.push(Chat::LeavedChannelMessagesCountSmall(
-                        LeavedChannelMessagesCountSmall {
-                            channel,
-                            messages: messages
-                                .into_iter()
-                                .map(Into::into)
-                                .collect::<Vec<_>>()
-                                .into(),
-                        },
-                    ));
+        if rules.members {
+            let members = match client.get_members(packed).await {
+                Ok(members) => members,
+                Err(err) => {
+                    error!(parent: &span, %err, "Error while get chat members");
                     continue;
                 }
+            };
+            trace!(parent: &span, ?members, "Members are fetched");
 
-                let last_message = messages.remove(0);
-                if last_message_is_old(
-                    &last_message,
-                    now_time,
-                    OLD_MESSAGE_ELAPSED_DAYS * CHANNEL_ELAPSED_MULTIPLIER,
-                ) {
-                    debug!(parent: &span, "Found an old leaved channel by last message");
-
-                    chats.push(Chat::LeavedChannelMessageOld(LeavedChannelMessageOld {
-                        channel,
-                        message: last_message.into(),
-                    }));
-                } else if old_messages_count_limit_reached(
-                    &messages,
-                    ELAPSED_DAYS_BETWEEN_OLD_MESSAGES * CHANNEL_ELAPSED_MULTIPLIER,
-                ) {
-                    debug!(parent: &span, "Found an old leaved channel by last messages which are periodically sent with high delay");
-
-                    chats.push(Chat::LeavedChannelMessagesOld(LeavedChannelMessagesOld {
-                        channel,
-                        messages: messages
-                            .into_iter()
-                            .map(Into::into)
-                            .collect::<Vec<_>>()
-                            .into(),
-                    }));
+            let peak = rules.members_history.as_ref().and_then(|history| {
+                let mut history = history.borrow_mut();
+                let peak = history.peak(chat_id);
+                if let Some(count) = members.count {
+                    history.record(chat_id, count, now);
                 }
+                peak
+            });
+
+            let found: Option<fn(MembersGone) -> Chat> = if only_you_left(&members) {
+                Some(Chat::OnlyYouLeft)
+            } else if admins_gone(&members) {
+                Some(Chat::AdminsGone)
+            } else if is_shrinking(&members, peak) {
+                Some(Chat::MembersShrinking)
+            } else {
+                None
+            };
+
+            if let Some(found) = found {
+                debug!(parent: &span, "Found a ghost chat by its members");
+
+                let stats = chat_stats(client, packed, Some(&history), now, &span).await;
+                chats.push(candidate.members_gone(found, members, peak, stats));
             }
-            enums::Chat::Forbidden(_) => {
-                trace!(parent: &span, "Group forbidden");
-            }
-            enums::Chat::ChannelForbidden(_) => {
-                trace!(parent: &span, "Channel forbidden");
+        }
+    }
+
+    Ok(chats)
+}
+
+#[instrument(skip_all)]
+pub async fn get_left_chats(
+    client: &impl TelegramApi,
+    takeout_id: i64,
+    ignored: &IgnoreMatcher,
+    filter: &ChatFilter,
+) -> Result<Vec<Chat>, InvocationError> {
+    let mut chats = vec![];
+
+    let now = client.now();
+
+    let left_chats = client.get_left_chats(takeout_id).await?;
+    for (num, left_chat) in left_chats.into_iter().enumerate() {
+        let (chat_id, username, title) = match left_chat {
+            LeftChat::Empty { id } => (id, None, None),
+            LeftChat::Chat(ref chat) => (chat.id, chat.username.as_deref(), Some(&*chat.title)),
+        };
+
+        let span = span!(Level::DEBUG, "iter", chat_id, num = num + 1);
+        let _guard = span.enter();
+
+        if ignored.is_ignored(chat_id, username, title) {
+            trace!(parent: &span, "Chat is ignored");
+            continue;
+        }
+        if !filter.matches_left(&left_chat) {
+            trace!(parent: &span, "Chat is filtered out");
+            continue;
+        }
+
+        let chat = match left_chat {
+            LeftChat::Empty { id } => {
+                debug!(parent: &span, "Found an old chat by empty chat");
+
+                chats.push(Chat::Empty(Empty { id }));
+                continue;
             }
+            LeftChat::Chat(chat) => chat,
+        };
+        let is_group = chat.ty == PackedType::Chat;
+
+        if chat.creator {
+            debug!(parent: &span, "Found an old chat in which you're the creator");
+
+            let stats = chat_stats(client, chat.packed(), None, now, &span).await;
+            chats.push(if is_group {
+                Chat::CreatorLeaved(CreatorLeaved { chat, stats })
+            } else {
+                Chat::ChannelCreatorLeaved(ChannelCreatorLeaved {
+                    channel: chat,
+                    stats,
+                })
+            });
+            continue;
+        }
+
+        let Some(history) = fetch_messages(client, chat.packed(), &span).await else {
+            continue;
         };
+        let mut human = human_messages(&history.messages);
+
+        if messages_count_is_too_small(human.len()) {
+            debug!(parent: &span, "Human messages count in the leaved chat is too small");
+
+            let stats = chat_stats(client, chat.packed(), Some(&history), now, &span).await;
+            let messages = messages_display(history.messages);
+            chats.push(if is_group {
+                Chat::LeavedMessagesCountSmall(LeavedMessagesCountSmall {
+                    chat,
+                    messages,
+                    stats,
+                })
+            } else {
+                Chat::LeavedChannelMessagesCountSmall(LeavedChannelMessagesCountSmall {
+                    channel: chat,
+                    messages,
+                    stats,
+                })
+            });
+        } else if last_message_is_old(
+            &human[0],
+            now,
+            OLD_MESSAGE_ELAPSED_DAYS * CHANNEL_ELAPSED_MULTIPLIER,
+        ) {
+            debug!(parent: &span, "Found an old leaved chat by last message");
+
+            let stats = chat_stats(client, chat.packed(), Some(&history), now, &span).await;
+            let message = human.swap_remove(0).into();
+            chats.push(if is_group {
+                Chat::LeavedMessageOld(LeavedMessageOld {
+                    chat,
+                    message,
+                    stats,
+                })
+            } else {
+                Chat::LeavedChannelMessageOld(LeavedChannelMessageOld {
+                    channel: chat,
+                    message,
+                    stats,
+                })
+            });
+        } else if old_messages_count_limit_reached(
+            &human,
+            ELAPSED_DAYS_BETWEEN_OLD_MESSAGES * CHANNEL_ELAPSED_MULTIPLIER,
+        ) {
+            debug!(parent: &span, "Found an old leaved chat by last messages which are periodically sent with high delay");
+
+            let stats = chat_stats(client, chat.packed(), Some(&history), now, &span).await;
+            let messages = messages_display(human);
+            chats.push(if is_group {
+                Chat::LeavedMessagesOld(LeavedMessagesOld {
+                    chat,
+                    messages,
+                    stats,
+                })
+            } else {
+                Chat::LeavedChannelMessagesOld(LeavedChannelMessagesOld {
+                    channel: chat,
+                    messages,
+                    stats,
+                })
+            });
+        }
     }
 
     Ok(chats)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use chrono::Duration;
+
+    use super::*;
+    use crate::{
+        client::{
+            analyze::{ArchiveFilter, MembersHistory},
+            api::fake::{self, FakeApi},
+            folders::Folder,
+        },
+        configs::IgnoreList,
+    };
+
+    fn reasons(chats: &[Chat]) -> Vec<(i64, &'static str)> {
+        chats
+            .iter()
+            .map(|chat| (chat.id(), chat.reason()))
+            .collect()
+    }
+
+    fn old_chats() -> FakeApi {
+        FakeApi::default()
+            .with_dialog(
+                fake::chat(PackedType::Chat, 1, "Group"),
+                fake::messages(&[40, 41]),
+            )
+            .with_archived_dialog(
+                fake::chat(PackedType::Megagroup, 2, "Archived supergroup"),
+                fake::messages(&[70, 71]),
+            )
+    }
+
+    #[tokio::test]
+    async fn joined_chats_are_classified() {
+        let api = FakeApi::default()
+            .with_dialog(
+                fake::chat(PackedType::Chat, 1, "Old group"),
+                fake::messages(&[40, 41]),
+            )
+            .with_dialog(
+                fake::chat(PackedType::Broadcast, 2, "Channel"),
+                fake::messages(&[40, 41]),
+            )
+            .with_dialog(fake::chat(PackedType::Megagroup, 3, "Empty"), vec![])
+            .with_dialog(
+                fake::chat(PackedType::Megagroup, 4, "Single message"),
+                fake::messages(&[1]),
+            )
+            .with_dialog(
+                fake::chat(PackedType::Megagroup, 5, "Active"),
+                fake::messages(&[0, 1, 2, 3]),
+            )
+            .with_dialog(
+                fake::chat(PackedType::Megagroup, 6, "Periodic"),
+                fake::messages(&[1, 62, 123, 184, 245, 306]),
+            );
+
+        let chats = get_chats(
+            &api,
+            &IgnoreMatcher::default(),
+            &ChatFilter::default(),
+            &Rules::default(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            reasons(&chats),
+            [
+                (1, "Last message too old"),
+                (3, "Messages empty"),
+                (4, "Messages count too small"),
+                (6, "Last messages too old"),
+            ],
+        );
+        assert!(chats.iter().all(|chat| !chat.is_left()));
+    }
+
+    #[tokio::test]
+    async fn only_human_messages_count_as_activity() {
+        let api = FakeApi::default()
+            .with_dialog(
+                fake::chat(PackedType::Megagroup, 1, "Bot spam over old talk"),
+                vec![
+                    fake::bot_message(6, 0),
+                    fake::service_message(5, 1),
+                    fake::bot_message(4, 2),
+                    fake::message(3, 70),
+                    fake::message(2, 71),
+                ],
+            )
+            .with_dialog(
+                fake::chat(PackedType::Megagroup, 2, "Bots only"),
+                vec![
+                    fake::bot_message(4, 0),
+                    fake::bot_message(3, 1),
+                    fake::service_message(2, 2),
+                    fake::bot_message(1, 3),
+                ],
+            )
+            .with_dialog(
+                fake::chat(PackedType::Megagroup, 3, "Active with bots"),
+                vec![
+                    fake::bot_message(5, 0),
+                    fake::message(4, 0),
+                    fake::message(3, 1),
+                    fake::message(2, 2),
+                    fake::message(1, 3),
+                ],
+            );
+
+        let chats = get_chats(
+            &api,
+            &IgnoreMatcher::default(),
+            &ChatFilter::default(),
+            &Rules::default(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            reasons(&chats),
+            [(1, "Last message too old"), (2, "Messages count too small"),],
+        );
+        assert_eq!(chats[0].messages()[0].0.id, 3);
+        assert_eq!(chats[1].messages().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn activity_is_sampled_over_the_window() {
+        let mut busy_then_quiet = (1..=15).collect::<Vec<_>>();
+        busy_then_quiet.push(400);
+        let api = FakeApi::default()
+            .with_dialog(
+                fake::chat(PackedType::Megagroup, 1, "Busy then quiet"),
+                fake::messages(&busy_then_quiet),
+            )
+            .with_dialog(
+                fake::chat(PackedType::Megagroup, 2, "Steady"),
+                fake::messages(&(0..16).map(|index| 1 + index * 20).collect::<Vec<_>>()),
+            );
+        let rules = Rules {
+            activity_months: Some(6),
+            ..Rules::default()
+        };
+
+        let chats = get_chats(
+            &api,
+            &IgnoreMatcher::default(),
+            &ChatFilter::default(),
+            &rules,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(reasons(&chats), [(1, "Activity too low")]);
+        assert_eq!(
+            chats[0].activity().unwrap().to_string(),
+            "Activity(per_month=[15+, 0, 0, 0, 0, 0])",
+        );
+
+        let chats = get_chats(
+            &api,
+            &IgnoreMatcher::default(),
+            &ChatFilter::default(),
+            &Rules::default(),
+        )
+        .await
+        .unwrap();
+
+        assert!(chats.is_empty());
+    }
+
+    #[tokio::test]
+    async fn own_last_message_is_searched_in_groups() {
+        let mut recently_written = fake::messages(&[0, 1, 2]);
+        recently_written.insert(1, fake::own_message(10, 1));
+        let mut long_ago_written = fake::messages(&[0, 1, 2]);
+        long_ago_written.push(fake::own_message(10, 100));
+        let api = FakeApi::default()
+            .with_dialog(
+                fake::chat(PackedType::Megagroup, 1, "Recently written"),
+                recently_written,
+            )
+            .with_dialog(
+                fake::chat(PackedType::Megagroup, 2, "Long ago written"),
+                long_ago_written,
+            )
+            .with_dialog(
+                fake::chat(PackedType::Chat, 3, "Never written"),
+                fake::messages(&[0, 1, 2]),
+            )
+            .with_dialog(
+                fake::chat(PackedType::Broadcast, 4, "Channel"),
+                fake::messages(&[0, 1, 2]),
+            );
+        let rules = Rules {
+            own_message_days: Some(90),
+            ..Rules::default()
+        };
+
+        let chats = get_chats(
+            &api,
+            &IgnoreMatcher::default(),
+            &ChatFilter::default(),
+            &rules,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            reasons(&chats),
+            [
+                (2, "Your last message too old"),
+                (3, "Your last message too old"),
+            ],
+        );
+        assert_eq!(chats[0].messages()[0].0.id, 10);
+        assert!(chats[1].messages().is_empty());
+    }
+
+    #[tokio::test]
+    async fn unread_chats_are_found_by_last_read_message() {
+        let messages = fake::messages(&[0, 1, 2, 3, 75, 76]);
+        let mut api = FakeApi::default()
+            .with_dialog(
+                fake::chat(PackedType::Megagroup, 1, "Read long ago"),
+                messages.clone(),
+            )
+            .with_dialog(
+                fake::chat(PackedType::Megagroup, 2, "Read recently"),
+                messages.clone(),
+            )
+            .with_dialog(
+                fake::chat(PackedType::Megagroup, 3, "Never read"),
+                messages.clone(),
+            )
+            .with_dialog(fake::chat(PackedType::Megagroup, 4, "Few unread"), messages);
+        for (dialog, (unread_count, read_inbox_max_id)) in
+            api.dialogs
+                .iter_mut()
+                .zip([(1000, 2), (1000, 5), (1000, 0), (10, 0)])
+        {
+            dialog.unread_count = unread_count;
+            dialog.read_inbox_max_id = read_inbox_max_id;
+        }
+        let rules = Rules {
+            min_unread: Some(500),
+            ..Rules::default()
+        };
+
+        let chats = get_chats(
+            &api,
+            &IgnoreMatcher::default(),
+            &ChatFilter::default(),
+            &rules,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            reasons(&chats),
+            [(1, "Not followed anymore"), (3, "Not followed anymore")],
+        );
+        assert_eq!(chats[0].unread_count(), Some(1000));
+        assert_eq!(chats[0].messages()[0].0.id, 2);
+        assert!(chats[1].messages().is_empty());
+    }
+
+    #[tokio::test]
+    async fn ghost_chats_are_found_by_members() {
+        let mut api = FakeApi::default();
+        for (id, title) in [
+            (1, "Bot spam"),
+            (2, "Abandoned"),
+            (3, "Shrinking"),
+            (4, "Alive"),
+            (5, "Hidden"),
+        ] {
+            api = api.with_dialog(
+                fake::chat(PackedType::Megagroup, id, title),
+                fake::messages(&[0, 1, 2]),
+            );
+        }
+        for (id, count, bots, admins) in [
+            (1, 3, 2, Some(1)),
+            (2, 50, 1, Some(0)),
+            (3, 60, 0, Some(2)),
+            (4, 100, 0, Some(2)),
+        ] {
+            api.members.insert(
+                id,
+                Members {
+                    count: Some(count),
+                    bots,
+                    admins,
+                },
+            );
+        }
+        let mut history = MembersHistory::default();
+        history.record(3, 100, Utc::now() - Duration::days(30));
+        history.record(4, 100, Utc::now() - Duration::days(30));
+        let rules = Rules {
+            members: true,
+            members_history: Some(RefCell::new(history)),
+            ..Rules::default()
+        };
+
+        let chats = get_chats(
+            &api,
+            &IgnoreMatcher::default(),
+            &ChatFilter::default(),
+            &rules,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            reasons(&chats),
+            [
+                (1, "Only you and bots left"),
+                (2, "Admins gone"),
+                (3, "Members leaving"),
+            ],
+        );
+        let history = rules.members_history.unwrap().into_inner();
+        assert_eq!(history.peak(1), Some(3));
+        assert_eq!(history.peak(3), Some(100));
+        assert_eq!(history.peak(5), None);
+    }
+
+    #[tokio::test]
+    async fn found_chats_have_stats() {
+        let busy_before = (0..20).map(|index| 65 + index * 5).collect::<Vec<_>>();
+        let api = FakeApi::default()
+            .with_dialog(
+                fake::chat(PackedType::Chat, 1, "Old group"),
+                vec![
+                    fake::message(4, 40),
+                    fake::own_message(3, 100),
+                    fake::message(2, 200),
+                    fake::message(1, 400),
+                ],
+            )
+            .with_dialog(
+                fake::chat(PackedType::Megagroup, 2, "Busy before"),
+                fake::messages(&busy_before),
+            );
+
+        let chats = get_chats(
+            &api,
+            &IgnoreMatcher::default(),
+            &ChatFilter::default(),
+            &Rules::default(),
+        )
+        .await
+        .unwrap();
+        let stats = chats
+            .iter()
+            .map(|chat| {
+                let stats = chat.stats().unwrap();
+
+                (
+                    stats.total,
+                    [
+                        stats.last_7_days,
+                        stats.last_30_days,
+                        stats.last_90_days,
+                        stats.last_365_days,
+                    ],
+                    stats.own_last_message_date.is_some(),
+                    stats.senders,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            stats,
+            [(4, [0, 0, 1, 3], true, 2), (20, [0, 0, 5, 20], false, 1)]
+        );
+    }
+
+    #[tokio::test]
+    async fn private_chats_are_skipped() {
+        let mut api = FakeApi::default().with_dialog(
+            fake::chat(PackedType::Megagroup, 1, "Private"),
+            fake::messages(&[1]),
+        );
+        api.private_chats.insert(1);
+
+        let chats = get_chats(
+            &api,
+            &IgnoreMatcher::default(),
+            &ChatFilter::default(),
+            &Rules::default(),
+        )
+        .await
+        .unwrap();
+
+        assert!(chats.is_empty());
+    }
+
+    #[tokio::test]
+    async fn ignored_and_filtered_out_chats_are_skipped() {
+        let api = old_chats().with_dialog(
+            fake::chat(PackedType::Megagroup, 3, "Supergroup"),
+            fake::messages(&[70, 71]),
+        );
+        let ignored = IgnoreList {
+            ids: [2].into(),
+            ..IgnoreList::default()
+        }
+        .matcher()
+        .unwrap();
+        let filter = ChatFilter {
+            kinds: vec![PackedType::Megagroup],
+            ..ChatFilter::default()
+        };
+
+        let chats = get_chats(&api, &ignored, &filter, &Rules::default())
+            .await
+            .unwrap();
+
+        assert_eq!(reasons(&chats), [(3, "Last message too old")]);
+    }
+
+    #[tokio::test]
+    async fn archive_and_folder_filters_are_respected() {
+        let api = old_chats();
+
+        for (archive, expected) in [
+            (ArchiveFilter::Any, &[1, 2][..]),
+            (ArchiveFilter::Only, &[2]),
+            (ArchiveFilter::Exclude, &[1]),
+        ] {
+            let filter = ChatFilter {
+                archive,
+                ..ChatFilter::default()
+            };
+            let chats = get_chats(&api, &IgnoreMatcher::default(), &filter, &Rules::default())
+                .await
+                .unwrap();
+
+            assert_eq!(
+                chats.iter().map(Chat::id).collect::<Vec<_>>(),
+                expected,
+                "{archive:?}",
+            );
+        }
+
+        let filter = ChatFilter {
+            folder: Some(Folder::with_chats(
+                "Work",
+                &[api.archived_dialogs[0].chat.packed()],
+            )),
+            ..ChatFilter::default()
+        };
+        let chats = get_chats(&api, &IgnoreMatcher::default(), &filter, &Rules::default())
+            .await
+            .unwrap();
+
+        assert_eq!(reasons(&chats), [(2, "Last message too old")]);
+    }
+
+    #[tokio::test]
+    async fn left_chats_are_classified() {
+        let creator = ChatInfo {
+            creator: true,
+            ..fake::chat(PackedType::Broadcast, 2, "My channel")
+        };
+        let mut api = FakeApi::default()
+            .with_left_chat(LeftChat::Empty { id: 1 }, vec![])
+            .with_left_chat(LeftChat::Chat(creator), vec![])
+            .with_left_chat(
+                LeftChat::Chat(fake::chat(PackedType::Chat, 3, "Old group")),
+                fake::messages(&[70, 71]),
+            )
+            .with_left_chat(
+                LeftChat::Chat(fake::chat(PackedType::Megagroup, 4, "Single message")),
+                fake::messages(&[1]),
+            )
+            .with_left_chat(
+                LeftChat::Chat(fake::chat(PackedType::Megagroup, 5, "Private")),
+                fake::messages(&[1]),
+            )
+            .with_left_chat(
+                LeftChat::Chat(fake::chat(PackedType::Broadcast, 6, "Active")),
+                fake::messages(&[40, 41]),
+            );
+        api.private_chats.insert(5);
+
+        let chats = get_left_chats(&api, 1, &IgnoreMatcher::default(), &ChatFilter::default())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            reasons(&chats),
+            [
+                (1, "Empty"),
+                (2, "Leaved as creator"),
+                (3, "Last message too old"),
+                (4, "Messages count too small"),
+            ],
+        );
+        assert!(chats.iter().all(Chat::is_left));
+
+        let filter = ChatFilter {
+            archive: ArchiveFilter::Only,
+            ..ChatFilter::default()
+        };
+        let chats = get_left_chats(&api, 1, &IgnoreMatcher::default(), &filter)
+            .await
+            .unwrap();
+
+        assert!(chats.is_empty());
+    }
+}
@@ -0,0 +1,280 @@
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use grammers_session::{PackedChat, PackedType};
+use serde::{Deserialize, Serialize};
+
+use super::{models::Chat, stats::ChatStats};
+
+#[derive(Debug)]
+pub enum ReportError {
+    Read {
+        path: PathBuf,
+        source: io::Error,
+    },
+    Parse {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+    Write {
+        path: PathBuf,
+        source: io::Error,
+    },
+    Serialize(serde_json::Error),
+    OtherProfile {
+        path: PathBuf,
+        report_profile: Box<str>,
+        profile: Box<str>,
+    },
+}
+
+impl Display for ReportError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ReportError::Read { path, source } => write!(
+                f,
+                "Can't read report `{path}`: {source}",
+                path = path.display(),
+            ),
+            ReportError::Parse { path, source } => write!(
+                f,
+                "Report `{path}` is invalid: {source}",
+                path = path.display(),
+            ),
+            ReportError::Write { path, source } => write!(
+                f,
+                "Can't write report `{path}`: {source}",
+                path = path.display(),
+            ),
+            ReportError::Serialize(err) => write!(f, "Can't serialize report: {err}"),
+            ReportError::OtherProfile {
+                path,
+                report_profile,
+                profile,
+            } => write!(
+                f,
+                "Report `{path}` is saved for profile `{report_profile}`, but profile `{profile}` is used. Select it with `--profile {report_profile}`",
+                path = path.display(),
+            ),
+        }
+    }
+}
+
+impl Error for ReportError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ReportError::Read { source, .. } | ReportError::Write { source, .. } => Some(source),
+            ReportError::Parse { source, .. } | ReportError::Serialize(source) => Some(source),
+            ReportError::OtherProfile { .. } => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportChatKind {
+    Group,
+    Megagroup,
+    Broadcast,
+    Gigagroup,
+}
+
+impl ReportChatKind {
+    /// `None` for users and bots
+    pub const fn from_packed_type(ty: PackedType) -> Option<Self> {
+        match ty {
+            PackedType::Chat => Some(ReportChatKind::Group),
+            PackedType::Megagroup => Some(ReportChatKind::Megagroup),
+            PackedType::Broadcast => Some(ReportChatKind::Broadcast),
+            PackedType::Gigagroup => Some(ReportChatKind::Gigagroup),
+            PackedType::User | PackedType::Bot => None,
+        }
+    }
+
+    pub const fn packed_type(self) -> PackedType {
+        match self {
+            ReportChatKind::Group => PackedType::Chat,
+            ReportChatKind::Megagroup => PackedType::Megagroup,
+            ReportChatKind::Broadcast => PackedType::Broadcast,
+            ReportChatKind::Gigagroup => PackedType::Gigagroup,
+        }
+    }
+}
+
+/// See [`ChatStats`], dates are Unix timestamps
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ReportStats {
+    pub total: usize,
+    pub last_7_days: usize,
+    pub last_30_days: usize,
+    pub last_90_days: usize,
+    pub last_365_days: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_message_date: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub own_last_message_date: Option<i64>,
+    pub senders: usize,
+}
+
+impl From<&ChatStats> for ReportStats {
+    fn from(stats: &ChatStats) -> Self {
+        ReportStats {
+            total: stats.total,
+            last_7_days: stats.last_7_days,
+            last_30_days: stats.last_30_days,
+            last_90_days: stats.last_90_days,
+            last_365_days: stats.last_365_days,
+            last_message_date: stats.last_message_date.map(|date| date.timestamp()),
+            own_last_message_date: stats.own_last_message_date.map(|date| date.timestamp()),
+            senders: stats.senders,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ReportChat {
+    pub id: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub access_hash: Option<i64>,
+    /// Unknown for empty chats
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<ReportChatKind>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<Box<str>>,
+    pub reason: Box<str>,
+    /// Whether you aren't a member of the chat anymore
+    pub left: bool,
+    /// Unknown for empty chats and chats which history can't be fetched
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stats: Option<ReportStats>,
+    /// Known only for chats found by unread messages
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unread_count: Option<i32>,
+    /// Profile the chat is found for in the report of all profiles
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<Box<str>>,
+}
+
+impl ReportChat {
+    /// Chat to make requests with. It's unknown for empty chats.
+    pub const fn packed(&self) -> Option<PackedChat> {
+        let Some(kind) = self.kind else {
+            return None;
+        };
+
+        Some(PackedChat {
+            ty: kind.packed_type(),
+            id: self.id,
+            access_hash: self.access_hash,
+        })
+    }
+}
+
+impl From<&Chat> for ReportChat {
+    fn from(chat: &Chat) -> Self {
+        let kind = chat
+            .packed()
+            .and_then(|packed| ReportChatKind::from_packed_type(packed.ty));
+
+        ReportChat {
+            id: chat.id(),
+            access_hash: chat.access_hash(),
+            kind,
+            title: chat.title().map(Into::into),
+            reason: chat.reason().into(),
+            left: chat.is_left(),
+            stats: chat.stats().map(Into::into),
+            unread_count: chat.unread_count(),
+            profile: None,
+        }
+    }
+}
+
+/// Chats found by the analysis, saved to act on them later
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct Report {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<Box<str>>,
+    pub chats: Vec<ReportChat>,
+}
+
+impl Report {
+    pub fn new(profile: Option<&str>, chats: &[Chat]) -> Self {
+        Report {
+            profile: profile.map(Into::into),
+            chats: chats.iter().map(Into::into).collect(),
+        }
+    }
+
+    /// Report of the chats of every profile, each tagged with its profile
+    pub fn merged<'a>(
+        profiles: impl IntoIterator<Item = (&'a str, impl IntoIterator<Item = &'a Chat>)>,
+    ) -> Self {
+        let chats = profiles
+            .into_iter()
+            .flat_map(|(profile, chats)| {
+                chats.into_iter().map(move |chat| ReportChat {
+                    profile: Some(profile.into()),
+                    ..chat.into()
+                })
+            })
+            .collect();
+
+        Report {
+            profile: None,
+            chats,
+        }
+    }
+
+    /// Report of the profile, since IDs of another account's chats are useless or point to other
+    /// chats. Reports saved without a profile are loaded for any of them, and only the chats of
+    /// the profile are kept from the report of all profiles.
+    pub fn load(path: &Path, profile: &str) -> Result<Self, ReportError> {
+        let raw = fs::read_to_string(path).map_err(|source| ReportError::Read {
+            path: path.to_path_buf(),
+            source,
+        })?;
+
+        let report: Self = serde_json::from_str(&raw).map_err(|source| ReportError::Parse {
+            path: path.to_path_buf(),
+            source,
+        })?;
+
+        match report.profile {
+            Some(ref report_profile) if **report_profile != *profile => {
+                Err(ReportError::OtherProfile {
+                    path: path.to_path_buf(),
+                    report_profile: report_profile.clone(),
+                    profile: profile.into(),
+                })
+            }
+            Some(_) => Ok(report),
+            None => Ok(Report {
+                profile: None,
+                chats: report
+                    .chats
+                    .into_iter()
+                    .filter(|chat| chat.profile.as_deref().is_none_or(|tag| tag == profile))
+                    .collect(),
+            }),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), ReportError> {
+        let raw = serde_json::to_string_pretty(self).map_err(ReportError::Serialize)?;
+
+        match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => fs::create_dir_all(dir),
+            _ => Ok(()),
+        }
+        .and_then(|()| fs::write(path, raw))
+        .map_err(|source| ReportError::Write {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+}
@@ -0,0 +1,23 @@
+use std::cell::RefCell;
+
+use super::members::MembersHistory;
+
+/// Optional rules of the analysis. Disabled rules make no extra requests.
+#[derive(Default)]
+pub struct Rules {
+    /// Number of 30-day months to sample the history of joined chats over, see
+    /// [`super::activity::sample_activity`]
+    pub activity_months: Option<usize>,
+    /// Number of days since your last message in joined groups to find them by, regardless of
+    /// how active they're
+    pub own_message_days: Option<i64>,
+    /// Number of unread messages in joined chats to find them by if you haven't read them for a
+    /// long time
+    pub min_unread: Option<i32>,
+    /// Whether to fetch members of joined chats to find the ones with only you and bots left or
+    /// without admins
+    pub members: bool,
+    /// Member counts of the previous analyses to find shrinking chats by. Counts of this analysis
+    /// are added to it, so it's saved afterwards.
+    pub members_history: Option<RefCell<MembersHistory>>,
+}
@@ -0,0 +1,113 @@
+use std::{
+    collections::HashSet,
+    fmt::{self, Display, Formatter},
+};
+
+use chrono::{DateTime, Duration, Utc};
+use grammers_client::client::bots::InvocationError;
+use grammers_session::PackedChat;
+
+use crate::client::api::{History, TelegramApi};
+
+/// Numbers about the history of the found chat
+#[derive(Clone, Debug, Default)]
+pub struct ChatStats {
+    /// Messages in the whole history
+    pub total: usize,
+    pub last_7_days: usize,
+    pub last_30_days: usize,
+    pub last_90_days: usize,
+    pub last_365_days: usize,
+    pub last_message_date: Option<DateTime<Utc>>,
+    /// `None` if you've never written to the chat
+    pub own_last_message_date: Option<DateTime<Utc>>,
+    /// Distinct senders of the last messages, channel posts are counted as one sender
+    pub senders: usize,
+}
+
+fn format_date(date: Option<DateTime<Utc>>) -> String {
+    date.map_or("never".to_owned(), |date| date.to_string())
+}
+
+impl Display for ChatStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Stats(total={total}, last_7_days={last_7_days}, last_30_days={last_30_days}, last_90_days={last_90_days}, last_365_days={last_365_days}, last_message={last_message}, own_last_message={own_last_message}, senders={senders})",
+            total = self.total,
+            last_7_days = self.last_7_days,
+            last_30_days = self.last_30_days,
+            last_90_days = self.last_90_days,
+            last_365_days = self.last_365_days,
+            last_message = format_date(self.last_message_date),
+            own_last_message = format_date(self.own_last_message_date),
+            senders = self.senders,
+        )
+    }
+}
+
+/// Messages sent in the last days. They're counted by the last messages if they reach the date,
+/// so only active chats take a request.
+async fn count_since(
+    client: &impl TelegramApi,
+    chat: PackedChat,
+    history: &History,
+    limit: usize,
+    days: i64,
+    now: DateTime<Utc>,
+) -> Result<usize, InvocationError> {
+    let date = now - Duration::days(days);
+    let is_sampled = history.messages.len() < limit
+        || history
+            .messages
+            .last()
+            .is_some_and(|message| message.date < date);
+
+    if is_sampled {
+        Ok(history
+            .messages
+            .iter()
+            .filter(|message| message.date >= date)
+            .count())
+    } else {
+        client.count_messages_since(chat, date).await
+    }
+}
+
+/// Stats of the chat. `history` are its last messages fetched with the limit, they're fetched
+/// here if the chat is found without them.
+pub async fn fetch_stats(
+    client: &impl TelegramApi,
+    chat: PackedChat,
+    history: Option<&History>,
+    limit: usize,
+    now: DateTime<Utc>,
+) -> Result<ChatStats, InvocationError> {
+    let fetched;
+    let history = match history {
+        Some(history) => history,
+        None => {
+            fetched = client.get_messages(chat, limit).await?;
+            &fetched
+        }
+    };
+
+    Ok(ChatStats {
+        total: history.count,
+        last_7_days: count_since(client, chat, history, limit, 7, now).await?,
+        last_30_days: count_since(client, chat, history, limit, 30, now).await?,
+        last_90_days: count_since(client, chat, history, limit, 90, now).await?,
+        last_365_days: count_since(client, chat, history, limit, 365, now).await?,
+        last_message_date: history.messages.first().map(|message| message.date),
+        own_last_message_date: client
+            .get_own_last_message(chat)
+            .await?
+            .map(|message| message.date),
+        senders: history
+            .messages
+            .iter()
+            .map(|message| message.sender)
+            .collect::<HashSet<_>>()
+            .len(),
+    })
+}
@@ -0,0 +1,228 @@
+mod export;
+#[cfg(test)]
+pub mod fake;
+mod fixture;
+mod grammers;
+mod record;
+mod replay;
+
+use std::fmt::{self, Display, Formatter};
+
+use chrono::{DateTime, Utc};
+use grammers_client::client::bots::InvocationError;
+use grammers_session::{PackedChat, PackedType};
+use grammers_tl_types::enums;
+
+use super::folders::Folder;
+
+pub use export::{Export, ExportError};
+pub use fixture::FixtureError;
+pub use record::Recorder;
+pub use replay::Replay;
+
+/// Group or channel. Users and bots aren't analyzed, so they're never returned.
+#[derive(Clone, Debug)]
+pub struct ChatInfo {
+    pub ty: PackedType,
+    pub id: i64,
+    /// Unknown for small groups, they don't need it
+    pub access_hash: Option<i64>,
+    pub title: Box<str>,
+    pub username: Option<Box<str>>,
+    /// Telegram doesn't always send it for channels
+    pub members: Option<i32>,
+    /// Whether you're the creator of the chat
+    pub creator: bool,
+}
+
+impl ChatInfo {
+    pub const fn packed(&self) -> PackedChat {
+        PackedChat {
+            ty: self.ty,
+            id: self.id,
+            access_hash: self.access_hash,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Message {
+    pub id: i32,
+    pub date: DateTime<Utc>,
+    /// Empty for service messages and messages with media only
+    pub text: Box<str>,
+    /// Action of the service message
+    pub action: Option<enums::MessageAction>,
+    /// ID of the user or the chat the message is sent by, `None` for channel posts
+    pub sender: Option<i64>,
+    /// Whether the message is sent by a bot or via an inline bot
+    pub from_bot: bool,
+    pub forwarded: bool,
+}
+
+/// What the message is, so the activity is judged by human messages only
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MessageKind {
+    /// Join, leave, pin and other actions
+    Service,
+    Bot,
+    Forwarded,
+    Human,
+}
+
+impl Display for MessageKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            MessageKind::Service => write!(f, "service"),
+            MessageKind::Bot => write!(f, "bot"),
+            MessageKind::Forwarded => write!(f, "forwarded"),
+            MessageKind::Human => write!(f, "human"),
+        }
+    }
+}
+
+impl Message {
+    pub const fn kind(&self) -> MessageKind {
+        if self.action.is_some() {
+            MessageKind::Service
+        } else if self.from_bot {
+            MessageKind::Bot
+        } else if self.forwarded {
+            MessageKind::Forwarded
+        } else {
+            MessageKind::Human
+        }
+    }
+
+    pub const fn is_human(&self) -> bool {
+        matches!(self.kind(), MessageKind::Human)
+    }
+}
+
+/// Last messages of the chat
+#[derive(Clone, Debug, Default)]
+pub struct History {
+    /// Messages in the whole history
+    pub count: usize,
+    /// The latest first
+    pub messages: Vec<Message>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Dialog {
+    pub chat: ChatInfo,
+    /// `None` for the main list
+    pub folder_id: Option<i32>,
+    pub unread_count: i32,
+    pub unread_mark: bool,
+    /// ID of the last message you've read, `0` if you've read nothing
+    pub read_inbox_max_id: i32,
+    pub muted: bool,
+    pub last_message: Option<Message>,
+}
+
+/// Chat you're left. Forbidden chats are skipped, since nothing can be done with them.
+#[derive(Clone, Debug)]
+pub enum LeftChat {
+    /// Nothing is known about the chat except its ID
+    Empty {
+        id: i64,
+    },
+    Chat(ChatInfo),
+}
+
+/// Members of the chat from its full info
+#[derive(Clone, Debug, Default)]
+pub struct Members {
+    /// Including you, `None` if Telegram hides it
+    pub count: Option<i32>,
+    pub bots: usize,
+    /// Admins and the creator who aren't bots, including you. `None` if you can't see them, e.g.
+    /// admins of channels are hidden from subscribers.
+    pub admins: Option<usize>,
+}
+
+/// Requests to Telegram the analysis and the commands are made with. It's implemented by the real
+/// client and by [`fake::FakeApi`] to test them offline.
+pub trait TelegramApi {
+    /// Time the analysis is made at, dates of messages are compared with it
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+
+    /// Groups and channels of the main list
+    async fn get_dialogs(&self) -> Result<Vec<Dialog>, InvocationError>;
+
+    /// Groups and channels of the peer folder, e.g. the archive
+    async fn get_folder_dialogs(&self, folder_id: i32) -> Result<Vec<Dialog>, InvocationError>;
+
+    async fn get_messages(
+        &self,
+        chat: PackedChat,
+        limit: usize,
+    ) -> Result<History, InvocationError>;
+
+    /// Messages of the chat sent before the date, the latest first
+    async fn get_messages_before(
+        &self,
+        chat: PackedChat,
+        date: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<Vec<Message>, InvocationError>;
+
+    /// Number of messages of the chat sent since the date
+    async fn count_messages_since(
+        &self,
+        chat: PackedChat,
+        date: DateTime<Utc>,
+    ) -> Result<usize, InvocationError>;
+
+    /// Your last message in the chat, `None` if you've never written there
+    async fn get_own_last_message(
+        &self,
+        chat: PackedChat,
+    ) -> Result<Option<Message>, InvocationError>;
+
+    /// The message with the ID or the latest one before it if it's deleted, `None` if there are
+    /// no such messages
+    async fn get_message_up_to(
+        &self,
+        chat: PackedChat,
+        id: i32,
+    ) -> Result<Option<Message>, InvocationError>;
+
+    async fn get_members(&self, chat: PackedChat) -> Result<Members, InvocationError>;
+
+    async fn init_takeout_session(&self) -> Result<i64, InvocationError>;
+
+    async fn finish_takeout_session(
+        &self,
+        takeout_id: i64,
+        success: bool,
+    ) -> Result<bool, InvocationError>;
+
+    /// Channels and supergroups you're left. Requires the takeout session.
+    async fn get_left_chats(&self, takeout_id: i64) -> Result<Vec<LeftChat>, InvocationError>;
+
+    /// `None` if you have no rights to export it
+    async fn export_invite_link(
+        &self,
+        chat: PackedChat,
+    ) -> Result<Option<Box<str>>, InvocationError>;
+
+    async fn join_channel(&self, chat: PackedChat) -> Result<(), InvocationError>;
+
+    async fn leave_chat(&self, chat: PackedChat) -> Result<(), InvocationError>;
+
+    async fn delete_channel(&self, chat: PackedChat) -> Result<(), InvocationError>;
+
+    async fn archive_chat(&self, chat: PackedChat) -> Result<(), InvocationError>;
+
+    async fn mute_chat(&self, chat: PackedChat) -> Result<(), InvocationError>;
+
+    async fn get_folders(&self) -> Result<Vec<Folder>, InvocationError>;
+
+    /// Returns `true` if the folder is created, see [`super::folders::save_folder`]
+    async fn save_folder(&self, title: &str, chats: &[PackedChat])
+        -> Result<bool, InvocationError>;
+}
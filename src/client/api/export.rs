@@ -0,0 +1,518 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt::{self, Display, Formatter},
+    fs::File,
+    io::{self, BufReader},
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use grammers_client::client::bots::InvocationError;
+use grammers_session::{PackedChat, PackedType};
+use grammers_tl_types::enums;
+use serde::Deserialize;
+use tracing::{info, trace};
+
+use super::{ChatInfo, Dialog, History, LeftChat, Members, Message, TelegramApi};
+use crate::client::folders::Folder;
+
+#[derive(Debug)]
+pub enum ExportError {
+    Read {
+        path: PathBuf,
+        source: io::Error,
+    },
+    Parse {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+}
+
+impl Display for ExportError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ExportError::Read { path, source } => write!(
+                f,
+                "Can't read export `{path}`: {source}",
+                path = path.display(),
+            ),
+            ExportError::Parse { path, source } => write!(
+                f,
+                "Export `{path}` is invalid. Export the data in JSON format in Telegram Desktop: {source}",
+                path = path.display(),
+            ),
+        }
+    }
+}
+
+impl Error for ExportError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ExportError::Read { source, .. } => Some(source),
+            ExportError::Parse { source, .. } => Some(source),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum RawChatType {
+    PrivateGroup,
+    PrivateSupergroup,
+    PublicSupergroup,
+    PrivateChannel,
+    PublicChannel,
+    /// Personal chats, bots and saved messages aren't analyzed
+    #[serde(other)]
+    Other,
+}
+
+/// Text is either a string or a list of strings and formatted entities
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawText {
+    Plain(Box<str>),
+    Parts(Vec<RawTextPart>),
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawTextPart {
+    Plain(Box<str>),
+    Entity { text: Box<str> },
+}
+
+impl RawText {
+    fn into_text(self) -> Box<str> {
+        match self {
+            RawText::Plain(text) => text,
+            RawText::Parts(parts) => parts
+                .into_iter()
+                .map(|part| match part {
+                    RawTextPart::Plain(text) | RawTextPart::Entity { text } => text,
+                })
+                .collect::<Vec<_>>()
+                .concat()
+                .into(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct RawMessage {
+    id: i32,
+    #[serde(rename = "type")]
+    ty: Box<str>,
+    /// Local time without the timezone, used by old exports without `date_unixtime`
+    date: Box<str>,
+    date_unixtime: Option<Box<str>>,
+    action: Option<Box<str>>,
+    actor_id: Option<Box<str>>,
+    /// Like `user123` or `channel123`
+    from_id: Option<Box<str>>,
+    /// Name of the chat or the user the message is forwarded from
+    forwarded_from: Option<Box<str>>,
+    /// Username of the inline bot
+    via_bot: Option<Box<str>>,
+    text: Option<RawText>,
+}
+
+impl RawMessage {
+    fn date(&self) -> Option<DateTime<Utc>> {
+        match self.date_unixtime {
+            Some(ref timestamp) => DateTime::from_timestamp(timestamp.parse().ok()?, 0),
+            None => NaiveDateTime::parse_from_str(&self.date, "%Y-%m-%dT%H:%M:%S")
+                .ok()
+                .map(|date| date.and_utc()),
+        }
+    }
+
+    fn sender(&self) -> Option<i64> {
+        self.from_id
+            .as_deref()?
+            .trim_start_matches(char::is_alphabetic)
+            .parse()
+            .ok()
+    }
+
+    /// Whether it's the creation of the chat by the user
+    fn is_created_by(&self, user_id: i64) -> bool {
+        matches!(
+            self.action.as_deref(),
+            Some("create_group" | "create_channel")
+        ) && self.actor_id.as_deref() == Some(&*format!("user{user_id}"))
+    }
+}
+
+#[derive(Deserialize)]
+struct RawChat {
+    id: i64,
+    #[serde(rename = "type")]
+    ty: RawChatType,
+    #[serde(default)]
+    name: Option<Box<str>>,
+    #[serde(default)]
+    messages: Vec<RawMessage>,
+}
+
+#[derive(Deserialize)]
+struct RawChatList {
+    list: Vec<RawChat>,
+}
+
+#[derive(Deserialize)]
+struct RawPersonalInformation {
+    user_id: i64,
+}
+
+#[derive(Deserialize)]
+struct RawExport {
+    personal_information: Option<RawPersonalInformation>,
+    chats: Option<RawChatList>,
+    left_chats: Option<RawChatList>,
+}
+
+/// Chats from the JSON export of Telegram Desktop. It's a client that answers the requests of
+/// the analysis offline, requests that change something are skipped.
+pub struct Export {
+    dialogs: Vec<Dialog>,
+    left_chats: Vec<LeftChat>,
+    /// By chat ID, the latest message first
+    messages: HashMap<i64, Vec<Message>>,
+    /// Your ID, it's missing if the personal information isn't exported
+    user_id: Option<i64>,
+}
+
+impl Export {
+    pub fn load(path: &Path) -> Result<Self, ExportError> {
+        let file = File::open(path).map_err(|source| ExportError::Read {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let raw: RawExport =
+            serde_json::from_reader(BufReader::new(file)).map_err(|source| ExportError::Parse {
+                path: path.to_path_buf(),
+                source,
+            })?;
+
+        let user_id = raw.personal_information.map(|info| info.user_id);
+        let mut export = Export {
+            dialogs: vec![],
+            left_chats: vec![],
+            messages: HashMap::new(),
+            user_id,
+        };
+
+        for chat in raw.chats.map(|chats| chats.list).unwrap_or_default() {
+            let Some((chat, messages)) = convert_chat(chat, user_id) else {
+                continue;
+            };
+
+            export.dialogs.push(Dialog {
+                chat: chat.clone(),
+                folder_id: None,
+                unread_count: 0,
+                unread_mark: false,
+                read_inbox_max_id: messages.first().map_or(0, |message| message.id),
+                muted: false,
+                last_message: messages.first().cloned(),
+            });
+            export.messages.insert(chat.id, messages);
+        }
+        for chat in raw.left_chats.map(|chats| chats.list).unwrap_or_default() {
+            let Some((chat, messages)) = convert_chat(chat, user_id) else {
+                continue;
+            };
+
+            export.messages.insert(chat.id, messages);
+            export.left_chats.push(LeftChat::Chat(chat));
+        }
+
+        info!(
+            joined = export.dialogs.len(),
+            left = export.left_chats.len(),
+            "Chats are loaded from the export",
+        );
+
+        Ok(export)
+    }
+}
+
+/// Chat with its messages, the latest first. `None` for chats that aren't analyzed.
+fn convert_chat(chat: RawChat, user_id: Option<i64>) -> Option<(ChatInfo, Vec<Message>)> {
+    let ty = match chat.ty {
+        RawChatType::PrivateGroup => PackedType::Chat,
+        RawChatType::PrivateSupergroup | RawChatType::PublicSupergroup => PackedType::Megagroup,
+        RawChatType::PrivateChannel | RawChatType::PublicChannel => PackedType::Broadcast,
+        RawChatType::Other => {
+            trace!(id = chat.id, "Chat isn't a group or a channel");
+            return None;
+        }
+    };
+    let creator = user_id.is_some_and(|user_id| {
+        chat.messages
+            .iter()
+            .any(|message| message.is_created_by(user_id))
+    });
+
+    let mut messages = chat
+        .messages
+        .into_iter()
+        .filter_map(|message| {
+            Some(Message {
+                id: message.id,
+                date: message.date()?,
+                // Details of the actions aren't exported in the same form as Telegram sends them
+                action: (&*message.ty == "service").then_some(enums::MessageAction::Empty),
+                sender: message.sender(),
+                // Senders are exported without the bot flag
+                from_bot: message.via_bot.is_some(),
+                forwarded: message.forwarded_from.is_some(),
+                text: message.text.map_or_else(|| "".into(), RawText::into_text),
+            })
+        })
+        .collect::<Vec<_>>();
+    // Messages are exported from the oldest one
+    messages.reverse();
+
+    Some((
+        ChatInfo {
+            ty,
+            id: chat.id,
+            // Access hashes aren't exported
+            access_hash: None,
+            title: chat.name.unwrap_or_else(|| "Deleted chat".into()),
+            username: None,
+            members: None,
+            creator,
+        },
+        messages,
+    ))
+}
+
+fn skip(action: &str, chat: PackedChat) -> Result<(), InvocationError> {
+    info!(
+        id = chat.id,
+        "{action} isn't sent while the export is analyzed"
+    );
+
+    Ok(())
+}
+
+impl TelegramApi for Export {
+    async fn get_dialogs(&self) -> Result<Vec<Dialog>, InvocationError> {
+        Ok(self.dialogs.clone())
+    }
+
+    /// Folders and the archive aren't exported
+    async fn get_folder_dialogs(&self, _folder_id: i32) -> Result<Vec<Dialog>, InvocationError> {
+        Ok(vec![])
+    }
+
+    async fn get_messages(
+        &self,
+        chat: PackedChat,
+        limit: usize,
+    ) -> Result<History, InvocationError> {
+        Ok(self
+            .messages
+            .get(&chat.id)
+            .map(|messages| History {
+                count: messages.len(),
+                messages: messages.iter().take(limit).cloned().collect(),
+            })
+            .unwrap_or_default())
+    }
+
+    async fn get_messages_before(
+        &self,
+        chat: PackedChat,
+        date: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<Vec<Message>, InvocationError> {
+        Ok(self
+            .messages
+            .get(&chat.id)
+            .map(|messages| {
+                messages
+                    .iter()
+                    .filter(|message| message.date < date)
+                    .take(limit)
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    async fn count_messages_since(
+        &self,
+        chat: PackedChat,
+        date: DateTime<Utc>,
+    ) -> Result<usize, InvocationError> {
+        Ok(self.messages.get(&chat.id).map_or(0, |messages| {
+            messages
+                .iter()
+                .filter(|message| message.date >= date)
+                .count()
+        }))
+    }
+
+    async fn get_own_last_message(
+        &self,
+        chat: PackedChat,
+    ) -> Result<Option<Message>, InvocationError> {
+        let (Some(user_id), Some(messages)) = (self.user_id, self.messages.get(&chat.id)) else {
+            return Ok(None);
+        };
+
+        Ok(messages
+            .iter()
+            .find(|message| message.sender == Some(user_id))
+            .cloned())
+    }
+
+    async fn get_message_up_to(
+        &self,
+        chat: PackedChat,
+        id: i32,
+    ) -> Result<Option<Message>, InvocationError> {
+        Ok(self
+            .messages
+            .get(&chat.id)
+            .and_then(|messages| messages.iter().find(|message| message.id <= id).cloned()))
+    }
+
+    /// Members aren't exported
+    async fn get_members(&self, _chat: PackedChat) -> Result<Members, InvocationError> {
+        Ok(Members::default())
+    }
+
+    async fn init_takeout_session(&self) -> Result<i64, InvocationError> {
+        Ok(0)
+    }
+
+    async fn finish_takeout_session(
+        &self,
+        _takeout_id: i64,
+        _success: bool,
+    ) -> Result<bool, InvocationError> {
+        Ok(true)
+    }
+
+    async fn get_left_chats(&self, _takeout_id: i64) -> Result<Vec<LeftChat>, InvocationError> {
+        Ok(self.left_chats.clone())
+    }
+
+    async fn export_invite_link(
+        &self,
+        _chat: PackedChat,
+    ) -> Result<Option<Box<str>>, InvocationError> {
+        Ok(None)
+    }
+
+    async fn join_channel(&self, chat: PackedChat) -> Result<(), InvocationError> {
+        skip("Join", chat)
+    }
+
+    async fn leave_chat(&self, chat: PackedChat) -> Result<(), InvocationError> {
+        skip("Leave", chat)
+    }
+
+    async fn delete_channel(&self, chat: PackedChat) -> Result<(), InvocationError> {
+        skip("Delete", chat)
+    }
+
+    async fn archive_chat(&self, chat: PackedChat) -> Result<(), InvocationError> {
+        skip("Archive", chat)
+    }
+
+    async fn mute_chat(&self, chat: PackedChat) -> Result<(), InvocationError> {
+        skip("Mute", chat)
+    }
+
+    async fn get_folders(&self) -> Result<Vec<Folder>, InvocationError> {
+        Ok(vec![])
+    }
+
+    async fn save_folder(
+        &self,
+        title: &str,
+        _chats: &[PackedChat],
+    ) -> Result<bool, InvocationError> {
+        info!(title, "Folder isn't saved while the export is analyzed");
+
+        Ok(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use chrono::Duration;
+
+    use super::*;
+    use crate::{
+        client::{
+            analyze::{get_chats, get_left_chats, ChatFilter, Rules},
+            api::fake::TempFile,
+        },
+        configs::IgnoreMatcher,
+    };
+
+    const LAST_MESSAGES: i32 = 15;
+
+    fn raw_message(id: i32, days_ago: i64) -> String {
+        format!(
+            r#"{{"id": {id}, "type": "message", "date": "2020-01-01T00:00:00", "date_unixtime": "{timestamp}", "text": ["Hello, ", {{"type": "bold", "text": "world"}}]}}"#,
+            timestamp = (Utc::now() - Duration::days(days_ago)).timestamp(),
+        )
+    }
+
+    #[tokio::test]
+    async fn export_is_analyzed() {
+        let raw = format!(
+            r#"{{
+                "personal_information": {{"user_id": 7}},
+                "chats": {{"list": [
+                    {{"id": 1, "type": "personal_chat", "name": "Friend", "messages": [{old}]}},
+                    {{"id": 2, "type": "private_group", "name": "Stale group", "messages": [
+                        {{"id": 1, "type": "service", "date": "2020-01-01T00:00:00", "date_unixtime": "1577836800", "action": "create_group", "actor_id": "user7", "text": ""}},
+                        {old}
+                    ]}},
+                    {{"id": 3, "type": "public_supergroup", "name": "Active", "messages": [{fresh}]}}
+                ]}},
+                "left_chats": {{"list": [
+                    {{"id": 4, "type": "private_channel", "name": "Left channel", "messages": [{old}]}}
+                ]}}
+            }}"#,
+            old = raw_message(2, 400),
+            fresh = (1..=LAST_MESSAGES)
+                .map(|id| raw_message(id, 1))
+                .collect::<Vec<_>>()
+                .join(", "),
+        );
+        let file = TempFile::new("export");
+        fs::write(file.path(), raw).unwrap();
+        let export = Export::load(file.path()).unwrap();
+
+        assert_eq!(export.dialogs.len(), 2);
+        assert!(export.dialogs[0].chat.creator);
+        assert_eq!(export.messages[&3][0].id, LAST_MESSAGES);
+        assert_eq!(&*export.messages[&3][0].text, "Hello, world");
+
+        let ignored = IgnoreMatcher::default();
+        let filter = ChatFilter::default();
+        let chats = get_chats(&export, &ignored, &filter, &Rules::default())
+            .await
+            .unwrap();
+        let left_chats = get_left_chats(&export, 0, &ignored, &filter).await.unwrap();
+
+        assert_eq!(chats.iter().map(|chat| chat.id()).collect::<Vec<_>>(), [2]);
+        assert_eq!(
+            left_chats.iter().map(|chat| chat.id()).collect::<Vec<_>>(),
+            [4]
+        );
+    }
+}
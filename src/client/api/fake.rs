@@ -0,0 +1,395 @@
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    env, fs,
+    path::{Path, PathBuf},
+    process,
+};
+
+use chrono::{DateTime, Duration, Utc};
+use grammers_client::client::bots::InvocationError;
+use grammers_mtsender::RpcError;
+use grammers_session::{PackedChat, PackedType};
+use grammers_tl_types::enums;
+
+use super::{ChatInfo, Dialog, History, LeftChat, Members, Message, TelegramApi};
+use crate::client::folders::{Folder, ARCHIVE_FOLDER_ID};
+
+/// Request that changes something, in the order they're made
+#[derive(Debug, PartialEq, Eq)]
+pub enum Call {
+    FinishTakeout { success: bool },
+    Join(i64),
+    Leave(i64),
+    Delete(i64),
+    Archive(i64),
+    Mute(i64),
+    SaveFolder { title: Box<str>, ids: Vec<i64> },
+}
+
+/// In-memory account. Chats are identified by their IDs only.
+#[derive(Default)]
+pub struct FakeApi {
+    pub dialogs: Vec<Dialog>,
+    pub archived_dialogs: Vec<Dialog>,
+    /// History of the chats, the latest first
+    pub messages: HashMap<i64, Vec<Message>>,
+    /// Chats which history can't be fetched
+    pub private_chats: HashSet<i64>,
+    /// Members are unknown for chats missed here
+    pub members: HashMap<i64, Members>,
+    pub left_chats: Vec<LeftChat>,
+    pub invite_links: HashMap<i64, Box<str>>,
+    /// Chats every action fails for, as if the ID or the access hash is wrong
+    pub invalid_chats: HashSet<i64>,
+    pub folders: RefCell<Vec<(Box<str>, Vec<PackedChat>)>>,
+    pub calls: RefCell<Vec<Call>>,
+}
+
+fn rpc_error(code: i32, name: &str) -> InvocationError {
+    InvocationError::Rpc(RpcError {
+        code,
+        name: name.to_owned(),
+        value: None,
+        caused_by: None,
+    })
+}
+
+pub fn chat(ty: PackedType, id: i64, title: &str) -> ChatInfo {
+    ChatInfo {
+        ty,
+        id,
+        access_hash: (ty != PackedType::Chat).then_some(id * 10),
+        title: title.into(),
+        username: None,
+        members: None,
+        creator: false,
+    }
+}
+
+/// ID of the account the fake is logged in
+pub const SELF_ID: i64 = 42;
+
+/// Text message sent the days ago by the chat itself
+pub fn message(id: i32, days_ago: i64) -> Message {
+    Message {
+        id,
+        date: Utc::now() - Duration::days(days_ago),
+        text: format!("Message {id}").into(),
+        action: None,
+        sender: None,
+        from_bot: false,
+        forwarded: false,
+    }
+}
+
+/// Message sent the days ago by a bot
+pub fn bot_message(id: i32, days_ago: i64) -> Message {
+    Message {
+        sender: Some(SELF_ID + 1),
+        from_bot: true,
+        ..message(id, days_ago)
+    }
+}
+
+/// Service message of the action, e.g. a pin, made the days ago
+pub fn service_message(id: i32, days_ago: i64) -> Message {
+    Message {
+        text: "".into(),
+        action: Some(enums::MessageAction::PinMessage),
+        ..message(id, days_ago)
+    }
+}
+
+/// Text message sent the days ago by you
+pub fn own_message(id: i32, days_ago: i64) -> Message {
+    Message {
+        sender: Some(SELF_ID),
+        ..message(id, days_ago)
+    }
+}
+
+/// Messages sent the days ago, the latest first
+pub fn messages(days_ago: &[i64]) -> Vec<Message> {
+    days_ago
+        .iter()
+        .enumerate()
+        .map(|(index, days)| message((days_ago.len() - index) as i32, *days))
+        .collect()
+}
+
+/// History that's too old for any chat kind, even for a quiet supergroup
+pub fn old_messages() -> Vec<Message> {
+    messages(&[70, 71])
+}
+
+/// JSON file in the temporary directory, unique for the test process. It's removed on drop, so
+/// a failed test doesn't leave it behind.
+pub struct TempFile(PathBuf);
+
+impl TempFile {
+    pub fn new(name: &str) -> Self {
+        TempFile(env::temp_dir().join(format!(
+            "tg_old_chats_manager-{name}-{pid}.json",
+            pid = process::id(),
+        )))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        // The file isn't created by every test
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+/// Dialog of the main list with the latest message as the last one
+pub fn dialog(chat: ChatInfo, messages: &[Message]) -> Dialog {
+    Dialog {
+        chat,
+        folder_id: None,
+        unread_count: 0,
+        unread_mark: false,
+        read_inbox_max_id: messages.first().map_or(0, |message| message.id),
+        muted: false,
+        last_message: messages.first().cloned(),
+    }
+}
+
+impl FakeApi {
+    /// Add the chat to the main list
+    pub fn with_dialog(mut self, chat: ChatInfo, messages: Vec<Message>) -> Self {
+        self.dialogs.push(dialog(chat.clone(), &messages));
+        self.messages.insert(chat.id, messages);
+        self
+    }
+
+    pub fn with_archived_dialog(mut self, chat: ChatInfo, messages: Vec<Message>) -> Self {
+        self.archived_dialogs.push(Dialog {
+            folder_id: Some(ARCHIVE_FOLDER_ID),
+            ..dialog(chat.clone(), &messages)
+        });
+        self.messages.insert(chat.id, messages);
+        self
+    }
+
+    pub fn with_left_chat(mut self, chat: LeftChat, messages: Vec<Message>) -> Self {
+        if let LeftChat::Chat(ref chat) = chat {
+            self.messages.insert(chat.id, messages);
+        }
+        self.left_chats.push(chat);
+        self
+    }
+
+    pub fn calls(&self) -> Vec<Call> {
+        self.calls.take()
+    }
+
+    fn act(&self, chat: PackedChat, call: fn(i64) -> Call) -> Result<(), InvocationError> {
+        if self.invalid_chats.contains(&chat.id) {
+            return Err(rpc_error(400, "CHANNEL_INVALID"));
+        }
+
+        self.calls.borrow_mut().push(call(chat.id));
+        Ok(())
+    }
+}
+
+impl TelegramApi for FakeApi {
+    async fn get_dialogs(&self) -> Result<Vec<Dialog>, InvocationError> {
+        Ok(self.dialogs.clone())
+    }
+
+    async fn get_folder_dialogs(&self, folder_id: i32) -> Result<Vec<Dialog>, InvocationError> {
+        Ok(self
+            .archived_dialogs
+            .iter()
+            .filter(|dialog| dialog.folder_id == Some(folder_id))
+            .cloned()
+            .collect())
+    }
+
+    async fn get_messages(
+        &self,
+        chat: PackedChat,
+        limit: usize,
+    ) -> Result<History, InvocationError> {
+        if self.private_chats.contains(&chat.id) {
+            return Err(rpc_error(400, "CHANNEL_PRIVATE"));
+        }
+
+        Ok(self
+            .messages
+            .get(&chat.id)
+            .map(|messages| History {
+                count: messages.len(),
+                messages: messages.iter().take(limit).cloned().collect(),
+            })
+            .unwrap_or_default())
+    }
+
+    async fn get_messages_before(
+        &self,
+        chat: PackedChat,
+        date: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<Vec<Message>, InvocationError> {
+        if self.private_chats.contains(&chat.id) {
+            return Err(rpc_error(400, "CHANNEL_PRIVATE"));
+        }
+
+        Ok(self
+            .messages
+            .get(&chat.id)
+            .map(|messages| {
+                messages
+                    .iter()
+                    .filter(|message| message.date < date)
+                    .take(limit)
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    async fn count_messages_since(
+        &self,
+        chat: PackedChat,
+        date: DateTime<Utc>,
+    ) -> Result<usize, InvocationError> {
+        if self.private_chats.contains(&chat.id) {
+            return Err(rpc_error(400, "CHANNEL_PRIVATE"));
+        }
+
+        Ok(self.messages.get(&chat.id).map_or(0, |messages| {
+            messages
+                .iter()
+                .filter(|message| message.date >= date)
+                .count()
+        }))
+    }
+
+    async fn get_own_last_message(
+        &self,
+        chat: PackedChat,
+    ) -> Result<Option<Message>, InvocationError> {
+        if self.private_chats.contains(&chat.id) {
+            return Err(rpc_error(400, "CHANNEL_PRIVATE"));
+        }
+
+        Ok(self.messages.get(&chat.id).and_then(|messages| {
+            messages
+                .iter()
+                .find(|message| message.sender == Some(SELF_ID))
+                .cloned()
+        }))
+    }
+
+    async fn get_message_up_to(
+        &self,
+        chat: PackedChat,
+        id: i32,
+    ) -> Result<Option<Message>, InvocationError> {
+        if self.private_chats.contains(&chat.id) {
+            return Err(rpc_error(400, "CHANNEL_PRIVATE"));
+        }
+
+        Ok(self
+            .messages
+            .get(&chat.id)
+            .and_then(|messages| messages.iter().find(|message| message.id <= id).cloned()))
+    }
+
+    async fn get_members(&self, chat: PackedChat) -> Result<Members, InvocationError> {
+        if self.private_chats.contains(&chat.id) {
+            return Err(rpc_error(400, "CHANNEL_PRIVATE"));
+        }
+
+        Ok(self.members.get(&chat.id).cloned().unwrap_or_default())
+    }
+
+    async fn init_takeout_session(&self) -> Result<i64, InvocationError> {
+        Ok(1)
+    }
+
+    async fn finish_takeout_session(
+        &self,
+        _takeout_id: i64,
+        success: bool,
+    ) -> Result<bool, InvocationError> {
+        self.calls
+            .borrow_mut()
+            .push(Call::FinishTakeout { success });
+        Ok(true)
+    }
+
+    async fn get_left_chats(&self, _takeout_id: i64) -> Result<Vec<LeftChat>, InvocationError> {
+        Ok(self.left_chats.clone())
+    }
+
+    async fn export_invite_link(
+        &self,
+        chat: PackedChat,
+    ) -> Result<Option<Box<str>>, InvocationError> {
+        Ok(self.invite_links.get(&chat.id).cloned())
+    }
+
+    async fn join_channel(&self, chat: PackedChat) -> Result<(), InvocationError> {
+        self.act(chat, Call::Join)
+    }
+
+    async fn leave_chat(&self, chat: PackedChat) -> Result<(), InvocationError> {
+        self.act(chat, Call::Leave)
+    }
+
+    async fn delete_channel(&self, chat: PackedChat) -> Result<(), InvocationError> {
+        self.act(chat, Call::Delete)
+    }
+
+    async fn archive_chat(&self, chat: PackedChat) -> Result<(), InvocationError> {
+        self.act(chat, Call::Archive)
+    }
+
+    async fn mute_chat(&self, chat: PackedChat) -> Result<(), InvocationError> {
+        self.act(chat, Call::Mute)
+    }
+
+    async fn get_folders(&self) -> Result<Vec<Folder>, InvocationError> {
+        Ok(self
+            .folders
+            .borrow()
+            .iter()
+            .map(|(title, chats)| Folder::with_chats(title, chats))
+            .collect())
+    }
+
+    async fn save_folder(
+        &self,
+        title: &str,
+        chats: &[PackedChat],
+    ) -> Result<bool, InvocationError> {
+        self.calls.borrow_mut().push(Call::SaveFolder {
+            title: title.into(),
+            ids: chats.iter().map(|chat| chat.id).collect(),
+        });
+
+        let mut folders = self.folders.borrow_mut();
+        match folders
+            .iter_mut()
+            .find(|(existing, _)| existing.eq_ignore_ascii_case(title))
+        {
+            Some((_, existing_chats)) => {
+                *existing_chats = chats.to_vec();
+                Ok(false)
+            }
+            None => {
+                folders.push((title.into(), chats.to_vec()));
+                Ok(true)
+            }
+        }
+    }
+}
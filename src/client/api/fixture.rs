@@ -0,0 +1,282 @@
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fmt::{self, Display, Formatter},
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use chrono::DateTime;
+use grammers_tl_types::enums;
+use serde::{Deserialize, Serialize};
+
+use super::{ChatInfo, Dialog, LeftChat, Members, Message};
+use crate::client::{analyze::ReportChatKind, folders::Folder};
+
+#[derive(Debug)]
+pub enum FixtureError {
+    Read {
+        path: PathBuf,
+        source: io::Error,
+    },
+    Parse {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+    Write {
+        path: PathBuf,
+        source: io::Error,
+    },
+    Serialize(serde_json::Error),
+}
+
+impl Display for FixtureError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            FixtureError::Read { path, source } => write!(
+                f,
+                "Can't read fixture `{path}`: {source}",
+                path = path.display(),
+            ),
+            FixtureError::Parse { path, source } => write!(
+                f,
+                "Fixture `{path}` is invalid: {source}",
+                path = path.display(),
+            ),
+            FixtureError::Write { path, source } => write!(
+                f,
+                "Can't write fixture `{path}`: {source}",
+                path = path.display(),
+            ),
+            FixtureError::Serialize(err) => write!(f, "Can't serialize fixture: {err}"),
+        }
+    }
+}
+
+impl Error for FixtureError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            FixtureError::Read { source, .. } | FixtureError::Write { source, .. } => Some(source),
+            FixtureError::Parse { source, .. } | FixtureError::Serialize(source) => Some(source),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub(super) struct FixtureChat {
+    pub id: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub access_hash: Option<i64>,
+    pub kind: ReportChatKind,
+    pub title: Box<str>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<Box<str>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub members: Option<i32>,
+    #[serde(default)]
+    pub creator: bool,
+}
+
+impl From<&FixtureChat> for ChatInfo {
+    fn from(chat: &FixtureChat) -> Self {
+        ChatInfo {
+            ty: chat.kind.packed_type(),
+            id: chat.id,
+            access_hash: chat.access_hash,
+            title: chat.title.clone(),
+            username: chat.username.clone(),
+            members: chat.members,
+            creator: chat.creator,
+        }
+    }
+}
+
+/// Message without the action details, since they contain titles and IDs of users
+#[derive(Debug, Deserialize, Serialize)]
+pub(super) struct FixtureMessage {
+    pub id: i32,
+    /// Unix timestamp
+    pub date: i64,
+    #[serde(default, skip_serializing_if = "str::is_empty")]
+    pub text: Box<str>,
+    #[serde(default)]
+    pub service: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sender: Option<i64>,
+    #[serde(default)]
+    pub from_bot: bool,
+    #[serde(default)]
+    pub forwarded: bool,
+}
+
+impl From<&FixtureMessage> for Message {
+    fn from(message: &FixtureMessage) -> Self {
+        Message {
+            id: message.id,
+            date: DateTime::from_timestamp(message.date, 0).unwrap_or_default(),
+            text: message.text.clone(),
+            action: message.service.then_some(enums::MessageAction::Empty),
+            sender: message.sender,
+            from_bot: message.from_bot,
+            forwarded: message.forwarded,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub(super) struct FixtureDialog {
+    pub chat: FixtureChat,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub folder_id: Option<i32>,
+    #[serde(default)]
+    pub unread_count: i32,
+    #[serde(default)]
+    pub unread_mark: bool,
+    #[serde(default)]
+    pub read_inbox_max_id: i32,
+    #[serde(default)]
+    pub muted: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_message: Option<FixtureMessage>,
+}
+
+impl From<&FixtureDialog> for Dialog {
+    fn from(dialog: &FixtureDialog) -> Self {
+        Dialog {
+            chat: (&dialog.chat).into(),
+            folder_id: dialog.folder_id,
+            unread_count: dialog.unread_count,
+            unread_mark: dialog.unread_mark,
+            read_inbox_max_id: dialog.read_inbox_max_id,
+            muted: dialog.muted,
+            last_message: dialog.last_message.as_ref().map(Into::into),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(super) enum FixtureLeftChat {
+    Empty { id: i64 },
+    Chat(FixtureChat),
+}
+
+impl From<&FixtureLeftChat> for LeftChat {
+    fn from(chat: &FixtureLeftChat) -> Self {
+        match chat {
+            FixtureLeftChat::Empty { id } => LeftChat::Empty { id: *id },
+            FixtureLeftChat::Chat(chat) => LeftChat::Chat(chat.into()),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub(super) struct FixtureMembers {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub count: Option<i32>,
+    #[serde(default)]
+    pub bots: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub admins: Option<usize>,
+}
+
+impl From<&Members> for FixtureMembers {
+    fn from(members: &Members) -> Self {
+        FixtureMembers {
+            count: members.count,
+            bots: members.bots,
+            admins: members.admins,
+        }
+    }
+}
+
+impl From<&FixtureMembers> for Members {
+    fn from(members: &FixtureMembers) -> Self {
+        Members {
+            count: members.count,
+            bots: members.bots,
+            admins: members.admins,
+        }
+    }
+}
+
+/// Error returned by Telegram
+#[derive(Debug, Deserialize, Serialize)]
+pub(super) struct FixtureRpcError {
+    pub code: i32,
+    pub name: Box<str>,
+}
+
+/// Responses of Telegram recorded during the analysis. IDs of chats and senders, access hashes,
+/// titles, usernames, invite links and message texts are anonymized, dates and counts are kept
+/// as is.
+#[derive(Default, Deserialize, Serialize)]
+pub struct Fixture {
+    /// Unix timestamp of the analysis, the replay is analyzed at the same time
+    pub(super) recorded_at: i64,
+    /// `None` if the main list isn't requested
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(super) dialogs: Option<Vec<FixtureDialog>>,
+    /// By peer folder ID
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub(super) folder_dialogs: BTreeMap<i32, Vec<FixtureDialog>>,
+    /// By chat ID, the latest message first
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub(super) messages: BTreeMap<i64, Result<Vec<FixtureMessage>, FixtureRpcError>>,
+    /// Messages fetched by dates, merged by chat ID, the latest first. Dates of the requests
+    /// aren't kept, since they're counted from the time of the analysis.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub(super) history: BTreeMap<i64, Vec<FixtureMessage>>,
+    /// Messages in the whole history by chat ID
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub(super) counts: BTreeMap<i64, usize>,
+    /// Messages sent in the last days by chat ID and the number of days
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub(super) counts_since: BTreeMap<i64, BTreeMap<i64, usize>>,
+    /// By chat ID
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub(super) own_last_messages: BTreeMap<i64, Option<FixtureMessage>>,
+    /// Messages fetched by IDs, by chat ID and the requested message ID
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub(super) messages_up_to: BTreeMap<i64, BTreeMap<i32, Option<FixtureMessage>>>,
+    /// By chat ID
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub(super) members: BTreeMap<i64, FixtureMembers>,
+    /// `None` if left chats aren't requested
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(super) left_chats: Option<Vec<FixtureLeftChat>>,
+    /// By chat ID
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub(super) invite_links: BTreeMap<i64, Option<Box<str>>>,
+    /// Titles of the folders are kept, so the analysis with `--folder` can be replayed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(super) folders: Option<Vec<Folder>>,
+}
+
+impl Fixture {
+    pub fn load(path: &Path) -> Result<Self, FixtureError> {
+        let raw = fs::read_to_string(path).map_err(|source| FixtureError::Read {
+            path: path.to_path_buf(),
+            source,
+        })?;
+
+        serde_json::from_str(&raw).map_err(|source| FixtureError::Parse {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), FixtureError> {
+        let raw = serde_json::to_string_pretty(self).map_err(FixtureError::Serialize)?;
+
+        match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => fs::create_dir_all(dir),
+            _ => Ok(()),
+        }
+        .and_then(|()| fs::write(path, raw))
+        .map_err(|source| FixtureError::Write {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+}
@@ -0,0 +1,360 @@
+use chrono::{DateTime, Utc};
+use grammers_client::{client::bots::InvocationError, types, Client};
+use grammers_session::{PackedChat, PackedType};
+use grammers_tl_types::{self as tl, enums, types as tl_types};
+use tracing::trace;
+
+use super::{ChatInfo, Dialog, History, LeftChat, Members, Message, TelegramApi};
+use crate::client::{
+    archive, delete,
+    folders::{self, Folder},
+    invite_link, join, leave, members, mute, search,
+};
+
+const fn channel_type(megagroup: bool, gigagroup: bool) -> PackedType {
+    if megagroup {
+        PackedType::Megagroup
+    } else if gigagroup {
+        PackedType::Gigagroup
+    } else {
+        PackedType::Broadcast
+    }
+}
+
+fn is_muted(settings: &enums::PeerNotifySettings) -> bool {
+    let enums::PeerNotifySettings::Settings(settings) = settings;
+
+    settings
+        .mute_until
+        .is_some_and(|mute_until| i64::from(mute_until) > Utc::now().timestamp())
+}
+
+fn date(timestamp: i32) -> DateTime<Utc> {
+    DateTime::from_timestamp(timestamp.into(), 0).unwrap_or_default()
+}
+
+const fn peer_id(peer: &enums::Peer) -> i64 {
+    match peer {
+        enums::Peer::User(tl_types::PeerUser { user_id: id })
+        | enums::Peer::Chat(tl_types::PeerChat { chat_id: id })
+        | enums::Peer::Channel(tl_types::PeerChannel { channel_id: id }) => *id,
+    }
+}
+
+impl ChatInfo {
+    /// `None` for empty and forbidden chats
+    pub(crate) fn from_raw(chat: &enums::Chat) -> Option<Self> {
+        match chat {
+            enums::Chat::Chat(chat) => Some(ChatInfo {
+                ty: PackedType::Chat,
+                id: chat.id,
+                access_hash: None,
+                title: chat.title.as_str().into(),
+                username: None,
+                members: Some(chat.participants_count),
+                creator: chat.creator,
+            }),
+            enums::Chat::Channel(channel) => Some(ChatInfo::from_channel(channel)),
+            enums::Chat::Empty(_)
+            | enums::Chat::Forbidden(_)
+            | enums::Chat::ChannelForbidden(_) => None,
+        }
+    }
+
+    fn from_channel(channel: &tl_types::Channel) -> Self {
+        ChatInfo {
+            ty: channel_type(channel.megagroup, channel.gigagroup),
+            id: channel.id,
+            access_hash: channel.access_hash,
+            title: channel.title.as_str().into(),
+            username: channel.username.as_deref().map(Into::into),
+            members: channel.participants_count,
+            creator: channel.creator,
+        }
+    }
+}
+
+impl From<types::Message> for Message {
+    fn from(message: types::Message) -> Self {
+        Message {
+            id: message.id(),
+            date: message.date(),
+            text: message.text().into(),
+            action: message.action().cloned(),
+            sender: message.sender().map(|sender| sender.id()),
+            from_bot: message.via_bot_id().is_some()
+                || matches!(message.sender(), Some(types::Chat::User(ref user)) if user.is_bot()),
+            forwarded: message.forward_header().is_some(),
+        }
+    }
+}
+
+/// Whether the sender is a bot. It's looked up in the users of the response the message is from.
+fn is_bot(from_id: Option<&enums::Peer>, users: &[enums::User]) -> bool {
+    let Some(enums::Peer::User(tl_types::PeerUser { user_id })) = from_id else {
+        return false;
+    };
+
+    users
+        .iter()
+        .any(|user| matches!(user, enums::User::User(user) if user.id == *user_id && user.bot))
+}
+
+impl Message {
+    /// `None` for empty messages. `users` are the ones of the response the message is from.
+    pub(crate) fn from_raw(message: &enums::Message, users: &[enums::User]) -> Option<Self> {
+        match message {
+            enums::Message::Message(message) => Some(Message {
+                id: message.id,
+                date: date(message.date),
+                text: message.message.as_str().into(),
+                action: None,
+                sender: message.from_id.as_ref().map(peer_id),
+                from_bot: message.via_bot_id.is_some() || is_bot(message.from_id.as_ref(), users),
+                forwarded: message.fwd_from.is_some(),
+            }),
+            enums::Message::Service(message) => Some(Message {
+                id: message.id,
+                date: date(message.date),
+                text: "".into(),
+                action: Some(message.action.clone()),
+                sender: message.from_id.as_ref().map(peer_id),
+                from_bot: is_bot(message.from_id.as_ref(), users),
+                forwarded: false,
+            }),
+            enums::Message::Empty(_) => None,
+        }
+    }
+}
+
+impl Dialog {
+    /// Dialog from the raw response with its top message and the users of the response. `None`
+    /// if the chat isn't available.
+    pub(crate) fn from_raw(
+        dialog: &tl_types::Dialog,
+        chat: &enums::Chat,
+        top_message: Option<&enums::Message>,
+        users: &[enums::User],
+    ) -> Option<Self> {
+        Some(Dialog {
+            chat: ChatInfo::from_raw(chat)?,
+            folder_id: dialog.folder_id,
+            unread_count: dialog.unread_count,
+            unread_mark: dialog.unread_mark,
+            read_inbox_max_id: dialog.read_inbox_max_id,
+            muted: is_muted(&dialog.notify_settings),
+            last_message: top_message.and_then(|message| Message::from_raw(message, users)),
+        })
+    }
+
+    fn from_client(dialog: types::Dialog) -> Option<Self> {
+        let enums::Dialog::Dialog(ref raw) = dialog.raw else {
+            return None;
+        };
+        let chat = match dialog.chat {
+            types::Chat::User(_) => return None,
+            types::Chat::Group(ref group) => ChatInfo::from_raw(&group.raw)?,
+            types::Chat::Channel(ref channel) => ChatInfo::from_channel(&channel.raw),
+        };
+
+        Some(Dialog {
+            chat,
+            folder_id: raw.folder_id,
+            unread_count: raw.unread_count,
+            unread_mark: raw.unread_mark,
+            read_inbox_max_id: raw.read_inbox_max_id,
+            muted: is_muted(&raw.notify_settings),
+            last_message: dialog.last_message.map(Into::into),
+        })
+    }
+}
+
+impl TelegramApi for Client {
+    async fn get_dialogs(&self) -> Result<Vec<Dialog>, InvocationError> {
+        let mut dialogs = vec![];
+        let mut dialogs_iter = self.iter_dialogs();
+
+        while let Some(dialog) = dialogs_iter.next().await? {
+            dialogs.extend(Dialog::from_client(dialog));
+        }
+
+        Ok(dialogs)
+    }
+
+    async fn get_folder_dialogs(&self, folder_id: i32) -> Result<Vec<Dialog>, InvocationError> {
+        folders::get_folder_dialogs(self, folder_id).await
+    }
+
+    async fn get_messages(
+        &self,
+        chat: PackedChat,
+        limit: usize,
+    ) -> Result<History, InvocationError> {
+        let mut messages_iter = self.iter_messages(chat).limit(limit);
+        let mut messages = Vec::with_capacity(limit);
+
+        while let Some(message) = messages_iter.next().await? {
+            messages.push(message.into());
+        }
+
+        Ok(History {
+            // It's known from the first response, so it takes no request
+            count: messages_iter.total().await?,
+            messages,
+        })
+    }
+
+    async fn get_messages_before(
+        &self,
+        chat: PackedChat,
+        date: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<Vec<Message>, InvocationError> {
+        let mut messages_iter = self
+            .iter_messages(chat)
+            .max_date(date.timestamp() as i32)
+            .limit(limit);
+        let mut messages = Vec::with_capacity(limit);
+
+        while let Some(message) = messages_iter.next().await? {
+            messages.push(message.into());
+        }
+
+        Ok(messages)
+    }
+
+    async fn count_messages_since(
+        &self,
+        chat: PackedChat,
+        date: DateTime<Utc>,
+    ) -> Result<usize, InvocationError> {
+        search::count_messages_since(self, chat, date.timestamp() as i32).await
+    }
+
+    async fn get_own_last_message(
+        &self,
+        chat: PackedChat,
+    ) -> Result<Option<Message>, InvocationError> {
+        let messages = search::search_own_messages(self, chat, 1).await?;
+
+        // Your account isn't a bot, so the senders aren't needed
+        Ok(messages
+            .first()
+            .and_then(|message| Message::from_raw(message, &[])))
+    }
+
+    async fn get_message_up_to(
+        &self,
+        chat: PackedChat,
+        id: i32,
+    ) -> Result<Option<Message>, InvocationError> {
+        // Messages are iterated from the offset exclusively
+        let mut messages_iter = self.iter_messages(chat).offset_id(id + 1).limit(1);
+
+        Ok(messages_iter.next().await?.map(Into::into))
+    }
+
+    async fn get_members(&self, chat: PackedChat) -> Result<Members, InvocationError> {
+        members::get_members(self, chat).await
+    }
+
+    async fn init_takeout_session(&self) -> Result<i64, InvocationError> {
+        self.invoke(&tl::functions::account::InitTakeoutSession {
+            contacts: false,
+            message_users: false,
+            message_chats: true,
+            message_megagroups: true,
+            message_channels: true,
+            files: false,
+            file_max_size: None,
+        })
+        .await
+        .map(|enums::account::Takeout::Takeout(tl_types::account::Takeout { id })| id)
+    }
+
+    async fn finish_takeout_session(
+        &self,
+        takeout_id: i64,
+        success: bool,
+    ) -> Result<bool, InvocationError> {
+        self.invoke(&tl::functions::InvokeWithTakeout {
+            takeout_id,
+            query: tl::functions::account::FinishTakeoutSession { success },
+        })
+        .await
+    }
+
+    async fn get_left_chats(&self, takeout_id: i64) -> Result<Vec<LeftChat>, InvocationError> {
+        let chats = match self
+            .invoke(&tl::functions::InvokeWithTakeout {
+                takeout_id,
+                query: tl::functions::channels::GetLeftChannels { offset: 0 },
+            })
+            .await?
+        {
+            enums::messages::Chats::Chats(tl_types::messages::Chats { chats })
+            | enums::messages::Chats::Slice(tl_types::messages::ChatsSlice { chats, .. }) => chats,
+        };
+
+        Ok(chats
+            .iter()
+            .filter_map(|chat| match chat {
+                enums::Chat::Empty(chat) => Some(LeftChat::Empty { id: chat.id }),
+                chat => {
+                    let info = ChatInfo::from_raw(chat).map(LeftChat::Chat);
+                    if info.is_none() {
+                        trace!(chat_id = chat.id(), "Chat forbidden");
+                    }
+                    info
+                }
+            })
+            .collect())
+    }
+
+    async fn export_invite_link(
+        &self,
+        chat: PackedChat,
+    ) -> Result<Option<Box<str>>, InvocationError> {
+        Ok(
+            invite_link::get_chat_invite(self, chat.id, chat.access_hash)
+                .await?
+                .map(|chat_invite| chat_invite.link.into()),
+        )
+    }
+
+    async fn join_channel(&self, chat: PackedChat) -> Result<(), InvocationError> {
+        join::join_channel(self, chat.id, chat.access_hash).await
+    }
+
+    async fn leave_chat(&self, chat: PackedChat) -> Result<(), InvocationError> {
+        if chat.ty == PackedType::Chat {
+            leave::leave_group(self, chat.id).await
+        } else {
+            leave::leave_channel(self, chat.id, chat.access_hash).await
+        }
+    }
+
+    async fn delete_channel(&self, chat: PackedChat) -> Result<(), InvocationError> {
+        delete::delete_channel(self, chat.id, chat.access_hash).await
+    }
+
+    async fn archive_chat(&self, chat: PackedChat) -> Result<(), InvocationError> {
+        archive::archive_chat(self, chat).await
+    }
+
+    async fn mute_chat(&self, chat: PackedChat) -> Result<(), InvocationError> {
+        mute::mute_chat(self, chat).await
+    }
+
+    async fn get_folders(&self) -> Result<Vec<Folder>, InvocationError> {
+        folders::get_folders(self).await
+    }
+
+    async fn save_folder(
+        &self,
+        title: &str,
+        chats: &[PackedChat],
+    ) -> Result<bool, InvocationError> {
+        folders::save_folder(self, title, chats).await
+    }
+}
@@ -0,0 +1,483 @@
+use std::{cell::RefCell, cmp::Reverse, collections::HashMap, path::Path};
+
+use chrono::{DateTime, Utc};
+use grammers_client::client::bots::InvocationError;
+use grammers_mtsender::RpcError;
+use grammers_session::PackedChat;
+
+use super::{
+    fixture::{
+        Fixture, FixtureChat, FixtureDialog, FixtureError, FixtureLeftChat, FixtureMessage,
+        FixtureRpcError,
+    },
+    ChatInfo, Dialog, History, LeftChat, Members, Message, TelegramApi,
+};
+use crate::client::{analyze::ReportChatKind, folders::Folder};
+
+/// Replaces real chat IDs by sequential ones, so the same chat has the same ID in all responses
+#[derive(Default)]
+struct Anonymizer {
+    ids: HashMap<i64, i64>,
+}
+
+impl Anonymizer {
+    fn id(&mut self, id: i64) -> i64 {
+        let next_id = self.ids.len() as i64 + 1;
+
+        *self.ids.entry(id).or_insert(next_id)
+    }
+
+    fn chat(&mut self, chat: &ChatInfo) -> Option<FixtureChat> {
+        let id = self.id(chat.id);
+
+        Some(FixtureChat {
+            id,
+            access_hash: chat.access_hash.map(|_| id),
+            kind: ReportChatKind::from_packed_type(chat.ty)?,
+            title: format!("Chat {id}").into(),
+            username: chat.username.as_ref().map(|_| format!("chat{id}").into()),
+            members: chat.members,
+            creator: chat.creator,
+        })
+    }
+
+    fn message(&mut self, message: &Message) -> FixtureMessage {
+        FixtureMessage {
+            id: message.id,
+            date: message.date.timestamp(),
+            text: if message.text.is_empty() {
+                "".into()
+            } else {
+                format!("Message {id}", id = message.id).into()
+            },
+            service: message.action.is_some(),
+            sender: message.sender.map(|id| self.id(id)),
+            from_bot: message.from_bot,
+            forwarded: message.forwarded,
+        }
+    }
+
+    fn dialog(&mut self, dialog: &Dialog) -> Option<FixtureDialog> {
+        Some(FixtureDialog {
+            chat: self.chat(&dialog.chat)?,
+            folder_id: dialog.folder_id,
+            unread_count: dialog.unread_count,
+            unread_mark: dialog.unread_mark,
+            read_inbox_max_id: dialog.read_inbox_max_id,
+            muted: dialog.muted,
+            last_message: dialog
+                .last_message
+                .as_ref()
+                .map(|message| self.message(message)),
+        })
+    }
+
+    fn messages(&mut self, messages: &[Message]) -> Vec<FixtureMessage> {
+        messages
+            .iter()
+            .map(|message| self.message(message))
+            .collect()
+    }
+
+    fn dialogs(&mut self, dialogs: &[Dialog]) -> Vec<FixtureDialog> {
+        dialogs
+            .iter()
+            .filter_map(|dialog| self.dialog(dialog))
+            .collect()
+    }
+}
+
+/// Client that records the responses of the wrapped one to the fixture. Requests that change
+/// something are sent as is and aren't recorded.
+pub struct Recorder<'a, A> {
+    api: &'a A,
+    fixture: RefCell<Fixture>,
+    anonymizer: RefCell<Anonymizer>,
+}
+
+impl<'a, A: TelegramApi> Recorder<'a, A> {
+    pub fn new(api: &'a A) -> Self {
+        Recorder {
+            api,
+            fixture: RefCell::new(Fixture {
+                recorded_at: api.now().timestamp(),
+                ..Fixture::default()
+            }),
+            anonymizer: RefCell::default(),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), FixtureError> {
+        self.fixture.borrow().save(path)
+    }
+
+    fn anonymized_id(&self, id: i64) -> i64 {
+        self.anonymizer.borrow_mut().id(id)
+    }
+}
+
+impl<A: TelegramApi> TelegramApi for Recorder<'_, A> {
+    /// The time of the recording, so the analysis is the same as the replayed one
+    fn now(&self) -> DateTime<Utc> {
+        DateTime::from_timestamp(self.fixture.borrow().recorded_at, 0).unwrap_or_default()
+    }
+
+    async fn get_dialogs(&self) -> Result<Vec<Dialog>, InvocationError> {
+        let dialogs = self.api.get_dialogs().await?;
+
+        self.fixture.borrow_mut().dialogs = Some(self.anonymizer.borrow_mut().dialogs(&dialogs));
+
+        Ok(dialogs)
+    }
+
+    async fn get_folder_dialogs(&self, folder_id: i32) -> Result<Vec<Dialog>, InvocationError> {
+        let dialogs = self.api.get_folder_dialogs(folder_id).await?;
+
+        self.fixture
+            .borrow_mut()
+            .folder_dialogs
+            .insert(folder_id, self.anonymizer.borrow_mut().dialogs(&dialogs));
+
+        Ok(dialogs)
+    }
+
+    async fn get_messages(
+        &self,
+        chat: PackedChat,
+        limit: usize,
+    ) -> Result<History, InvocationError> {
+        let result = self.api.get_messages(chat, limit).await;
+
+        let id = self.anonymized_id(chat.id);
+        let recorded = match result {
+            Ok(ref history) => {
+                self.fixture.borrow_mut().counts.insert(id, history.count);
+
+                Ok(self.anonymizer.borrow_mut().messages(&history.messages))
+            }
+            Err(InvocationError::Rpc(RpcError { code, ref name, .. })) => Err(FixtureRpcError {
+                code,
+                name: name.as_str().into(),
+            }),
+            // Network errors aren't responses of Telegram
+            Err(_) => return result,
+        };
+        self.fixture.borrow_mut().messages.insert(id, recorded);
+
+        result
+    }
+
+    async fn get_messages_before(
+        &self,
+        chat: PackedChat,
+        date: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<Vec<Message>, InvocationError> {
+        let messages = self.api.get_messages_before(chat, date, limit).await?;
+
+        let id = self.anonymized_id(chat.id);
+        let recorded = self.anonymizer.borrow_mut().messages(&messages);
+        let mut fixture = self.fixture.borrow_mut();
+        let history = fixture.history.entry(id).or_default();
+        history.extend(recorded);
+        history.sort_by_key(|message| Reverse(message.id));
+        history.dedup_by_key(|message| message.id);
+
+        Ok(messages)
+    }
+
+    /// Recorded by the number of days, since the date depends on the time of the analysis
+    async fn count_messages_since(
+        &self,
+        chat: PackedChat,
+        date: DateTime<Utc>,
+    ) -> Result<usize, InvocationError> {
+        let count = self.api.count_messages_since(chat, date).await?;
+
+        let id = self.anonymized_id(chat.id);
+        self.fixture
+            .borrow_mut()
+            .counts_since
+            .entry(id)
+            .or_default()
+            .insert((self.now() - date).num_days(), count);
+
+        Ok(count)
+    }
+
+    async fn get_own_last_message(
+        &self,
+        chat: PackedChat,
+    ) -> Result<Option<Message>, InvocationError> {
+        let message = self.api.get_own_last_message(chat).await?;
+
+        let id = self.anonymized_id(chat.id);
+        let recorded = message
+            .as_ref()
+            .map(|message| self.anonymizer.borrow_mut().message(message));
+        self.fixture
+            .borrow_mut()
+            .own_last_messages
+            .insert(id, recorded);
+
+        Ok(message)
+    }
+
+    async fn get_message_up_to(
+        &self,
+        chat: PackedChat,
+        id: i32,
+    ) -> Result<Option<Message>, InvocationError> {
+        let message = self.api.get_message_up_to(chat, id).await?;
+
+        let chat_id = self.anonymized_id(chat.id);
+        let recorded = message
+            .as_ref()
+            .map(|message| self.anonymizer.borrow_mut().message(message));
+        self.fixture
+            .borrow_mut()
+            .messages_up_to
+            .entry(chat_id)
+            .or_default()
+            .insert(id, recorded);
+
+        Ok(message)
+    }
+
+    async fn get_members(&self, chat: PackedChat) -> Result<Members, InvocationError> {
+        let members = self.api.get_members(chat).await?;
+
+        let id = self.anonymized_id(chat.id);
+        self.fixture
+            .borrow_mut()
+            .members
+            .insert(id, (&members).into());
+
+        Ok(members)
+    }
+
+    async fn init_takeout_session(&self) -> Result<i64, InvocationError> {
+        self.api.init_takeout_session().await
+    }
+
+    async fn finish_takeout_session(
+        &self,
+        takeout_id: i64,
+        success: bool,
+    ) -> Result<bool, InvocationError> {
+        self.api.finish_takeout_session(takeout_id, success).await
+    }
+
+    async fn get_left_chats(&self, takeout_id: i64) -> Result<Vec<LeftChat>, InvocationError> {
+        let chats = self.api.get_left_chats(takeout_id).await?;
+
+        let recorded = {
+            let mut anonymizer = self.anonymizer.borrow_mut();
+
+            chats
+                .iter()
+                .filter_map(|chat| match chat {
+                    LeftChat::Empty { id } => Some(FixtureLeftChat::Empty {
+                        id: anonymizer.id(*id),
+                    }),
+                    LeftChat::Chat(chat) => anonymizer.chat(chat).map(FixtureLeftChat::Chat),
+                })
+                .collect()
+        };
+        self.fixture.borrow_mut().left_chats = Some(recorded);
+
+        Ok(chats)
+    }
+
+    async fn export_invite_link(
+        &self,
+        chat: PackedChat,
+    ) -> Result<Option<Box<str>>, InvocationError> {
+        let link = self.api.export_invite_link(chat).await?;
+
+        let id = self.anonymized_id(chat.id);
+        self.fixture.borrow_mut().invite_links.insert(
+            id,
+            link.as_ref()
+                .map(|_| format!("https://t.me/+chat{id}").into()),
+        );
+
+        Ok(link)
+    }
+
+    async fn join_channel(&self, chat: PackedChat) -> Result<(), InvocationError> {
+        self.api.join_channel(chat).await
+    }
+
+    async fn leave_chat(&self, chat: PackedChat) -> Result<(), InvocationError> {
+        self.api.leave_chat(chat).await
+    }
+
+    async fn delete_channel(&self, chat: PackedChat) -> Result<(), InvocationError> {
+        self.api.delete_channel(chat).await
+    }
+
+    async fn archive_chat(&self, chat: PackedChat) -> Result<(), InvocationError> {
+        self.api.archive_chat(chat).await
+    }
+
+    async fn mute_chat(&self, chat: PackedChat) -> Result<(), InvocationError> {
+        self.api.mute_chat(chat).await
+    }
+
+    async fn get_folders(&self) -> Result<Vec<Folder>, InvocationError> {
+        let folders = self.api.get_folders().await?;
+
+        let recorded = folders
+            .iter()
+            .cloned()
+            .map(|folder| folder.map_ids(|id| self.anonymized_id(id)))
+            .collect();
+        self.fixture.borrow_mut().folders = Some(recorded);
+
+        Ok(folders)
+    }
+
+    async fn save_folder(
+        &self,
+        title: &str,
+        chats: &[PackedChat],
+    ) -> Result<bool, InvocationError> {
+        self.api.save_folder(title, chats).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use grammers_session::PackedType;
+
+    use super::*;
+    use crate::{
+        client::{
+            analyze::{get_chats, get_left_chats, Chat, ChatFilter, Rules},
+            api::{
+                fake::{self, FakeApi, TempFile},
+                Replay,
+            },
+        },
+        configs::IgnoreMatcher,
+    };
+
+    async fn analyze(api: &impl TelegramApi) -> Vec<Chat> {
+        let ignored = IgnoreMatcher::default();
+        let filter = ChatFilter::default();
+
+        let mut chats = get_chats(api, &ignored, &filter, &Rules::default())
+            .await
+            .unwrap();
+        chats.extend(get_left_chats(api, 1, &ignored, &filter).await.unwrap());
+        chats
+    }
+
+    fn summary(chats: &[Chat]) -> Vec<(i64, Option<&str>, &'static str)> {
+        chats
+            .iter()
+            .map(|chat| (chat.id(), chat.title(), chat.reason()))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn replayed_analysis_is_anonymized() {
+        let mut api = FakeApi::default()
+            .with_dialog(
+                fake::chat(PackedType::Megagroup, 1001, "Secret supergroup"),
+                fake::old_messages(),
+            )
+            .with_dialog(
+                fake::chat(PackedType::Chat, 1002, "Private group"),
+                fake::messages(&[1]),
+            )
+            .with_archived_dialog(
+                fake::chat(PackedType::Broadcast, 1003, "Secret channel"),
+                fake::messages(&[0, 1, 2]),
+            )
+            .with_left_chat(
+                LeftChat::Chat(fake::chat(PackedType::Megagroup, 1004, "Secret left")),
+                fake::messages(&[1]),
+            );
+        api.private_chats.insert(1002);
+
+        let recorder = Recorder::new(&api);
+        analyze(&recorder).await;
+
+        let fixture = TempFile::new("fixture");
+        recorder.save(fixture.path()).unwrap();
+        let raw = fs::read_to_string(fixture.path()).unwrap();
+        let replay = Replay::load(fixture.path()).unwrap();
+
+        assert!(!raw.contains("Secret"));
+        for id in 1001..=1004 {
+            assert!(!raw.contains(&format!(" {id},")) && !raw.contains(&format!(" {id}\n")));
+        }
+        assert_eq!(
+            summary(&analyze(&replay).await),
+            [
+                (1, Some("Chat 1"), "Last message too old"),
+                (4, Some("Chat 4"), "Messages count too small"),
+            ],
+        );
+    }
+
+    /// Dates of the fixture moved to the past, as if it's recorded then
+    fn shift_dates(value: &mut serde_json::Value, seconds: i64) {
+        match value {
+            serde_json::Value::Object(map) => {
+                for (key, value) in map {
+                    match value.as_i64() {
+                        Some(date) if key == "date" || key == "recorded_at" => {
+                            *value = (date - seconds).into();
+                        }
+                        _ => shift_dates(value, seconds),
+                    }
+                }
+            }
+            serde_json::Value::Array(values) => {
+                for value in values {
+                    shift_dates(value, seconds);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    #[tokio::test]
+    async fn replay_is_analyzed_at_the_recording_time() {
+        let api = FakeApi::default()
+            .with_dialog(
+                fake::chat(PackedType::Megagroup, 1001, "Old supergroup"),
+                fake::old_messages(),
+            )
+            .with_dialog(
+                fake::chat(PackedType::Megagroup, 1002, "Active supergroup"),
+                fake::messages(&[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11]),
+            );
+
+        let recorder = Recorder::new(&api);
+        let recorded = summary(&analyze(&recorder).await)
+            .into_iter()
+            .map(|(_, _, reason)| reason)
+            .collect::<Vec<_>>();
+
+        let fixture = TempFile::new("fixture-shifted");
+        recorder.save(fixture.path()).unwrap();
+        let mut raw: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(fixture.path()).unwrap()).unwrap();
+        shift_dates(&mut raw, 400 * 24 * 60 * 60);
+        fs::write(fixture.path(), raw.to_string()).unwrap();
+        let replay = Replay::load(fixture.path()).unwrap();
+
+        let replayed = summary(&analyze(&replay).await)
+            .into_iter()
+            .map(|(_, _, reason)| reason)
+            .collect::<Vec<_>>();
+        assert_eq!(recorded, ["Last message too old"]);
+        assert_eq!(replayed, recorded);
+    }
+}
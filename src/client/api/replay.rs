@@ -0,0 +1,236 @@
+use std::{cmp::Reverse, path::Path};
+
+use chrono::{DateTime, Utc};
+use grammers_client::client::bots::InvocationError;
+use grammers_mtsender::RpcError;
+use grammers_session::PackedChat;
+use tracing::{info, warn};
+
+use super::{
+    fixture::{Fixture, FixtureError},
+    Dialog, History, LeftChat, Members, Message, TelegramApi,
+};
+use crate::client::folders::Folder;
+
+/// Responses missed in the fixture are returned as this error, e.g. if the analysis is replayed
+/// with other arguments than it's recorded
+fn not_recorded(request: &str) -> InvocationError {
+    warn!(request, "Response isn't recorded");
+
+    InvocationError::Rpc(RpcError {
+        code: 400,
+        name: "NOT_RECORDED".to_owned(),
+        value: None,
+        caused_by: None,
+    })
+}
+
+/// Client that returns the responses recorded by [`super::record::Recorder`] instead of sending
+/// the requests. Requests that change something are skipped.
+pub struct Replay {
+    fixture: Fixture,
+}
+
+impl Replay {
+    pub fn load(path: &Path) -> Result<Self, FixtureError> {
+        Ok(Replay {
+            fixture: Fixture::load(path)?,
+        })
+    }
+}
+
+fn skip(action: &str, chat: PackedChat) -> Result<(), InvocationError> {
+    info!(
+        id = chat.id,
+        "{action} isn't sent while the analysis is replayed"
+    );
+
+    Ok(())
+}
+
+impl TelegramApi for Replay {
+    fn now(&self) -> DateTime<Utc> {
+        DateTime::from_timestamp(self.fixture.recorded_at, 0).unwrap_or_default()
+    }
+
+    async fn get_dialogs(&self) -> Result<Vec<Dialog>, InvocationError> {
+        match self.fixture.dialogs {
+            Some(ref dialogs) => Ok(dialogs.iter().map(Into::into).collect()),
+            None => Err(not_recorded("dialogs")),
+        }
+    }
+
+    async fn get_folder_dialogs(&self, folder_id: i32) -> Result<Vec<Dialog>, InvocationError> {
+        match self.fixture.folder_dialogs.get(&folder_id) {
+            Some(dialogs) => Ok(dialogs.iter().map(Into::into).collect()),
+            None => Err(not_recorded("folder dialogs")),
+        }
+    }
+
+    async fn get_messages(
+        &self,
+        chat: PackedChat,
+        limit: usize,
+    ) -> Result<History, InvocationError> {
+        match self.fixture.messages.get(&chat.id) {
+            Some(Ok(messages)) => Ok(History {
+                count: self
+                    .fixture
+                    .counts
+                    .get(&chat.id)
+                    .copied()
+                    .unwrap_or(messages.len()),
+                messages: messages.iter().take(limit).map(Into::into).collect(),
+            }),
+            Some(Err(err)) => Err(InvocationError::Rpc(RpcError {
+                code: err.code,
+                name: err.name.to_string(),
+                value: None,
+                caused_by: None,
+            })),
+            None => Err(not_recorded("messages")),
+        }
+    }
+
+    /// Answered from all the recorded messages of the chat, since the dates of the requests
+    /// depend on the time of the analysis
+    async fn get_messages_before(
+        &self,
+        chat: PackedChat,
+        date: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<Vec<Message>, InvocationError> {
+        let latest = match self.fixture.messages.get(&chat.id) {
+            Some(Ok(messages)) => &messages[..],
+            _ => &[],
+        };
+        let Some(history) = self.fixture.history.get(&chat.id) else {
+            return Err(not_recorded("messages by date"));
+        };
+
+        let mut messages = latest
+            .iter()
+            .chain(history)
+            .map(Message::from)
+            .filter(|message| message.date < date)
+            .collect::<Vec<_>>();
+        messages.sort_by_key(|message| Reverse(message.id));
+        messages.dedup_by_key(|message| message.id);
+        messages.truncate(limit);
+
+        Ok(messages)
+    }
+
+    async fn count_messages_since(
+        &self,
+        chat: PackedChat,
+        date: DateTime<Utc>,
+    ) -> Result<usize, InvocationError> {
+        self.fixture
+            .counts_since
+            .get(&chat.id)
+            .and_then(|counts| counts.get(&(self.now() - date).num_days()))
+            .copied()
+            .ok_or_else(|| not_recorded("messages count"))
+    }
+
+    async fn get_own_last_message(
+        &self,
+        chat: PackedChat,
+    ) -> Result<Option<Message>, InvocationError> {
+        match self.fixture.own_last_messages.get(&chat.id) {
+            Some(message) => Ok(message.as_ref().map(Into::into)),
+            None => Err(not_recorded("own last message")),
+        }
+    }
+
+    async fn get_message_up_to(
+        &self,
+        chat: PackedChat,
+        id: i32,
+    ) -> Result<Option<Message>, InvocationError> {
+        match self
+            .fixture
+            .messages_up_to
+            .get(&chat.id)
+            .and_then(|messages| messages.get(&id))
+        {
+            Some(message) => Ok(message.as_ref().map(Into::into)),
+            None => Err(not_recorded("message by ID")),
+        }
+    }
+
+    async fn get_members(&self, chat: PackedChat) -> Result<Members, InvocationError> {
+        self.fixture
+            .members
+            .get(&chat.id)
+            .map(Into::into)
+            .ok_or_else(|| not_recorded("members"))
+    }
+
+    async fn init_takeout_session(&self) -> Result<i64, InvocationError> {
+        Ok(0)
+    }
+
+    async fn finish_takeout_session(
+        &self,
+        _takeout_id: i64,
+        _success: bool,
+    ) -> Result<bool, InvocationError> {
+        Ok(true)
+    }
+
+    async fn get_left_chats(&self, _takeout_id: i64) -> Result<Vec<LeftChat>, InvocationError> {
+        match self.fixture.left_chats {
+            Some(ref chats) => Ok(chats.iter().map(Into::into).collect()),
+            None => Err(not_recorded("left chats")),
+        }
+    }
+
+    async fn export_invite_link(
+        &self,
+        chat: PackedChat,
+    ) -> Result<Option<Box<str>>, InvocationError> {
+        match self.fixture.invite_links.get(&chat.id) {
+            Some(link) => Ok(link.clone()),
+            None => Err(not_recorded("invite link")),
+        }
+    }
+
+    async fn join_channel(&self, chat: PackedChat) -> Result<(), InvocationError> {
+        skip("Join", chat)
+    }
+
+    async fn leave_chat(&self, chat: PackedChat) -> Result<(), InvocationError> {
+        skip("Leave", chat)
+    }
+
+    async fn delete_channel(&self, chat: PackedChat) -> Result<(), InvocationError> {
+        skip("Delete", chat)
+    }
+
+    async fn archive_chat(&self, chat: PackedChat) -> Result<(), InvocationError> {
+        skip("Archive", chat)
+    }
+
+    async fn mute_chat(&self, chat: PackedChat) -> Result<(), InvocationError> {
+        skip("Mute", chat)
+    }
+
+    async fn get_folders(&self) -> Result<Vec<Folder>, InvocationError> {
+        self.fixture
+            .folders
+            .clone()
+            .ok_or_else(|| not_recorded("folders"))
+    }
+
+    async fn save_folder(
+        &self,
+        title: &str,
+        _chats: &[PackedChat],
+    ) -> Result<bool, InvocationError> {
+        info!(title, "Folder isn't saved while the analysis is replayed");
+
+        Ok(false)
+    }
+}
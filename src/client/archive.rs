@@ -0,0 +1,20 @@
+use grammers_client::{client::bots::InvocationError, Client};
+use grammers_session::PackedChat;
+use grammers_tl_types::{self as tl, enums, types};
+use tracing::instrument;
+
+use super::folders::ARCHIVE_FOLDER_ID;
+
+#[instrument(skip_all, fields(id = chat.id))]
+pub async fn archive_chat(client: &Client, chat: PackedChat) -> Result<(), InvocationError> {
+    client
+        .invoke(&tl::functions::folders::EditPeerFolders {
+            folder_peers: vec![enums::InputFolderPeer::Peer(types::InputFolderPeer {
+                peer: chat.to_input_peer(),
+                folder_id: ARCHIVE_FOLDER_ID,
+            })],
+        })
+        .await?;
+
+    Ok(())
+}
@@ -1,20 +1,174 @@
 use std::{
     error::Error,
+    fmt::{self, Display, Formatter},
     io::{self, Write as _},
+    time::{Duration, Instant},
 };
 
-use grammers_client::{Client, Config, InitParams, SignInError};
-use grammers_session::Session;
-use tracing::{debug, info, instrument, trace};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
 
+use grammers_client::{
+    client::bots::InvocationError, types::PasswordToken, Client, Config, InitParams, SignInError,
+};
+use grammers_mtsender::{AuthorizationError, RpcError};
+use grammers_tl_types::{self as tl, enums, types as tl_types};
+use qrcode::{render::unicode::Dense1x2, QrCode};
+use tokio::time::sleep;
+use tracing::{debug, error, info, instrument, trace};
+
+use super::session_file::{SessionError, SessionFile};
 use crate::configs::ClientConfig;
 
+const LOGIN_CODE_ATTEMPTS: usize = 3;
+const PASSWORD_ATTEMPTS: usize = 3;
+
+const QR_LOGIN_POLL_INTERVAL: Duration = Duration::from_secs(3);
+const QR_LOGIN_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Debug)]
+pub enum InitError {
+    Session(SessionError),
+    Connect(AuthorizationError),
+}
+
+impl Display for InitError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            InitError::Session(err) => write!(f, "{err}"),
+            InitError::Connect(err) => write!(f, "Can't connect to Telegram: {err}"),
+        }
+    }
+}
+
+impl Error for InitError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            InitError::Session(err) => Some(err),
+            InitError::Connect(err) => Some(err),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum AuthorizeError {
+    InvalidApiId,
+    InvalidPhoneNumber { phone_number: Box<str> },
+    QrLoginTimeout,
+    QrLoginMigrate { dc_id: i32 },
+    PasswordAttempts,
+    SwitchDc(InitError),
+    Input(io::Error),
+    SaveSession(SessionError),
+    Request(InvocationError),
+    LoginCode(AuthorizationError),
+    SignIn(SignInError),
+}
+
+impl AuthorizeError {
+    fn from_request(err: InvocationError) -> Self {
+        match err {
+            InvocationError::Rpc(RpcError { ref name, .. }) if name == "API_ID_INVALID" => {
+                AuthorizeError::InvalidApiId
+            }
+            err => AuthorizeError::Request(err),
+        }
+    }
+
+    fn from_login_code(err: AuthorizationError, phone_number: &str) -> Self {
+        match err {
+            AuthorizationError::Invoke(InvocationError::Rpc(RpcError { ref name, .. })) => {
+                match name.as_str() {
+                    "API_ID_INVALID" => AuthorizeError::InvalidApiId,
+                    "PHONE_NUMBER_INVALID" => AuthorizeError::InvalidPhoneNumber {
+                        phone_number: phone_number.into(),
+                    },
+                    _ => AuthorizeError::LoginCode(err),
+                }
+            }
+            err => AuthorizeError::LoginCode(err),
+        }
+    }
+}
+
+impl Display for AuthorizeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            AuthorizeError::InvalidApiId => write!(
+                f,
+                "Telegram rejected `api_id` and `api_hash` from the config. Check them on https://my.telegram.org/apps",
+            ),
+            AuthorizeError::InvalidPhoneNumber { phone_number } => write!(
+                f,
+                "Telegram rejected phone number `{phone_number}`. Check `phone_number` in the config",
+            ),
+            AuthorizeError::QrLoginTimeout => write!(
+                f,
+                "The QR code wasn't scanned in time. Run the command again to get a new one",
+            ),
+            AuthorizeError::QrLoginMigrate { dc_id } => write!(
+                f,
+                "Telegram redirected the QR login to another data center (DC {dc_id}) again. Run the command again",
+            ),
+            AuthorizeError::SwitchDc(err) => {
+                write!(f, "Can't switch to the data center of your account: {err}")
+            }
+            AuthorizeError::Input(err) => write!(f, "Can't read the input: {err}"),
+            AuthorizeError::SaveSession(err) => write!(f, "Can't save the session: {err}"),
+            AuthorizeError::Request(err) => {
+                write!(f, "Error while checking authorization info: {err}")
+            }
+            AuthorizeError::LoginCode(err) => write!(f, "Error while request login code: {err}"),
+            AuthorizeError::PasswordAttempts => write!(
+                f,
+                "The entered password of your 2FA is wrong {PASSWORD_ATTEMPTS} times. Run the command again to retry",
+            ),
+            AuthorizeError::SignIn(SignInError::InvalidCode) => {
+                write!(f, "The login code is wrong")
+            }
+            AuthorizeError::SignIn(SignInError::SignUpRequired { .. }) => write!(
+                f,
+                "There is no Telegram account with this phone number. Sign up in the official app first",
+            ),
+            AuthorizeError::SignIn(err) => write!(f, "Error while sign in: {err}"),
+        }
+    }
+}
+
+impl Error for AuthorizeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            AuthorizeError::InvalidApiId
+            | AuthorizeError::InvalidPhoneNumber { .. }
+            | AuthorizeError::QrLoginTimeout
+            | AuthorizeError::QrLoginMigrate { .. }
+            | AuthorizeError::PasswordAttempts => None,
+            AuthorizeError::SwitchDc(err) => Some(err),
+            AuthorizeError::Input(err) => Some(err),
+            AuthorizeError::SaveSession(err) => Some(err),
+            AuthorizeError::Request(err) => Some(err),
+            AuthorizeError::LoginCode(err) => Some(err),
+            AuthorizeError::SignIn(err) => Some(err),
+        }
+    }
+}
+
+pub fn read_line(prompt: &str) -> io::Result<Box<str>> {
+    print!("{prompt}");
+    io::stdout().flush()?;
+
+    let mut line = String::new();
+    if io::stdin().read_line(&mut line)? == 0 {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+
+    Ok(line.trim().to_owned().into_boxed_str())
+}
+
 #[instrument(skip_all)]
-pub async fn init(config: &ClientConfig) -> Client {
+pub async fn init(config: &ClientConfig, session_file: &SessionFile) -> Result<Client, InitError> {
     trace!("Load or create session file");
 
-    let session = Session::load_file_or_create(config.session_file_path())
-        .expect("Error while creating or writing to session file");
+    let session = session_file.load_or_create().map_err(InitError::Session)?;
 
     debug!("Connect client");
 
@@ -28,80 +182,348 @@ pub async fn init(config: &ClientConfig) -> Client {
         },
     })
     .await
-    .expect("Error during client authorization")
+    .map_err(InitError::Connect)
 }
 
-async fn sign_out_disconnect_on_err<T, E: Error>(client: &Client, val: Result<T, E>) -> T {
-    match val {
-        Ok(val) => val,
-        Err(err) => {
-            debug!("Sign out and disconnect");
-
-            drop(client.sign_out_disconnect().await);
+async fn sign_out_disconnect_on_err<T, E>(client: &Client, val: Result<T, E>) -> Result<T, E> {
+    if val.is_err() {
+        debug!("Sign out and disconnect");
 
-            panic!("Sign out and disconnect: {err}");
-        }
+        drop(client.sign_out_disconnect().await);
     }
+
+    val
+}
+
+async fn get_password_token(client: &Client) -> Result<PasswordToken, InvocationError> {
+    client
+        .invoke(&tl::functions::account::GetPassword {})
+        .await
+        .map(|enums::account::Password::Password(password)| PasswordToken::new(password))
 }
 
 #[instrument(skip_all)]
-pub async fn authorize(client: &Client, config: &ClientConfig) {
-    debug!("Check authorization info");
+async fn check_password(
+    client: &Client,
+    config: &ClientConfig,
+    mut token: PasswordToken,
+) -> Result<(), AuthorizeError> {
+    let mut config_password = config.password();
+    let mut attempt = 0;
 
-    if client
-        .is_authorized()
-        .await
-        .expect("Error while checking authorization info")
-    {
-        return;
+    loop {
+        let input_password = if let Some(password) = config_password.take() {
+            password.to_owned().into_boxed_str()
+        } else {
+            attempt += 1;
+
+            let prompt = match token.hint() {
+                Some(hint) => format!("Enter the password of your 2FA (hint: {hint}): "),
+                None => "Enter the password of your 2FA: ".to_owned(),
+            };
+            read_line(&prompt).map_err(AuthorizeError::Input)?
+        };
+
+        info!("Client check password");
+
+        match client.check_password(token, &*input_password).await {
+            Ok(_) => return Ok(()),
+            Err(SignInError::InvalidPassword) if attempt < PASSWORD_ATTEMPTS => {
+                if attempt == 0 {
+                    println!("The password from the config is wrong.");
+                } else {
+                    println!(
+                        "The password is wrong, try again ({left} attempts left).",
+                        left = PASSWORD_ATTEMPTS - attempt,
+                    );
+                }
+
+                debug!("Request new password token");
+
+                token = get_password_token(client)
+                    .await
+                    .map_err(AuthorizeError::Request)?;
+            }
+            // The password from the config gets interactive attempts, so only entered ones are
+            // wrong here
+            Err(SignInError::InvalidPassword) => return Err(AuthorizeError::PasswordAttempts),
+            Err(err) => return Err(AuthorizeError::SignIn(err)),
+        };
     }
+}
 
+#[instrument(skip_all)]
+async fn sign_in_by_code(
+    client: &Client,
+    config: &ClientConfig,
+    phone_number: &str,
+) -> Result<(), AuthorizeError> {
     info!("Request login code");
 
     let token = client
-        .request_login_code(config.phone_number())
+        .request_login_code(phone_number)
         .await
-        .expect("Error while request login code");
+        .map_err(|err| AuthorizeError::from_login_code(err, phone_number))?;
+
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+
+        let code = read_line("Enter the code you received on your Telegram account: ")
+            .map_err(AuthorizeError::Input)?;
 
-    print!("Enter the code you received on your Telegram account: ");
-    io::stdout().flush().unwrap();
+        info!("Client sign in");
+
+        match client.sign_in(&token, &code).await {
+            Ok(_) => return Ok(()),
+            Err(SignInError::InvalidCode) if attempt < LOGIN_CODE_ATTEMPTS => {
+                println!(
+                    "The code is wrong, try again ({left} attempts left).",
+                    left = LOGIN_CODE_ATTEMPTS - attempt,
+                );
+            }
+            Err(SignInError::PasswordRequired(token)) => {
+                return check_password(client, config, token).await;
+            }
+            Err(err) => return Err(AuthorizeError::SignIn(err)),
+        };
+    }
+}
 
-    let code = {
-        let mut code = String::new();
-        io::stdin().read_line(&mut code).unwrap();
-        code.trim().to_owned().into_boxed_str()
+fn print_login_qr_code(token: &[u8]) {
+    let url = format!("tg://login?token={}", URL_SAFE_NO_PAD.encode(token));
+
+    match QrCode::new(url.as_bytes()) {
+        Ok(code) => {
+            let image = code
+                .render::<Dense1x2>()
+                .dark_color(Dense1x2::Light)
+                .light_color(Dense1x2::Dark)
+                .build();
+
+            println!("{image}");
+        }
+        Err(err) => error!(%err, "Error while render QR code"),
     };
 
-    info!("Client sign in");
+    println!("Scan the QR code in Telegram on your phone (Settings > Devices > Link Desktop Device) or open the link on it: {url}");
+}
 
-    match client.sign_in(&token, &code).await {
-        Ok(_) => {}
-        Err(SignInError::PasswordRequired(password)) => {
-            let input_password = if let Some(password) = config.password() {
-                password.to_owned().into_boxed_str()
-            } else {
-                print!("Enter the password of your 2FA: ");
-                io::stdout().flush().unwrap();
+/// What to do with the response to the exported or the imported login token
+#[derive(Debug, PartialEq, Eq)]
+enum QrLoginStep {
+    /// Show the token and poll it until it's scanned
+    Show(Vec<u8>),
+    /// The account is served by another DC, so the token is imported there
+    Migrate { dc_id: i32, token: Vec<u8> },
+    /// `user_id` is `None` if Telegram asks to sign up
+    Done { user_id: Option<i64> },
+}
 
-                let mut password = String::new();
-                io::stdin().read_line(&mut password).unwrap();
-                password.trim().to_owned().into_boxed_str()
-            };
+impl From<enums::auth::LoginToken> for QrLoginStep {
+    fn from(token: enums::auth::LoginToken) -> Self {
+        match token {
+            enums::auth::LoginToken::Token(tl_types::auth::LoginToken { token, .. }) => {
+                QrLoginStep::Show(token)
+            }
+            enums::auth::LoginToken::MigrateTo(tl_types::auth::LoginTokenMigrateTo {
+                dc_id,
+                token,
+            }) => QrLoginStep::Migrate { dc_id, token },
+            enums::auth::LoginToken::Success(tl_types::auth::LoginTokenSuccess {
+                authorization,
+            }) => QrLoginStep::Done {
+                user_id: match authorization {
+                    enums::auth::Authorization::Authorization(tl_types::auth::Authorization {
+                        user: enums::User::User(tl_types::User { id, .. }),
+                        ..
+                    })
+                    | enums::auth::Authorization::Authorization(tl_types::auth::Authorization {
+                        user: enums::User::Empty(tl_types::UserEmpty { id }),
+                        ..
+                    }) => Some(id),
+                    enums::auth::Authorization::SignUpRequired(_) => None,
+                },
+            },
+        }
+    }
+}
+
+/// Client connected to the DC the account is served by. The DC is saved to the session as the
+/// one of the user, whose ID isn't known until the login is finished.
+async fn switch_dc(
+    client: &Client,
+    config: &ClientConfig,
+    session_file: &SessionFile,
+    dc_id: i32,
+) -> Result<Client, InitError> {
+    client.session().set_user(0, dc_id, false);
+    session_file
+        .save(client.session())
+        .map_err(InitError::Session)?;
 
-            info!("Client check password");
+    init(config, session_file).await
+}
+
+/// Returns the client connected to the DC of the account, it's another one than the passed
+/// client is connected to if the login is migrated
+#[instrument(skip_all)]
+async fn sign_in_by_qr(
+    mut client: Client,
+    config: &ClientConfig,
+    session_file: &SessionFile,
+) -> Result<Client, AuthorizeError> {
+    let started_at = Instant::now();
+    let mut shown_token = None;
+    // DC the token is imported in and the token
+    let mut migrated_to: Option<(i32, Vec<u8>)> = None;
 
-            sign_out_disconnect_on_err(
-                client,
-                client.check_password(password, &*input_password).await,
-            )
-            .await;
+    loop {
+        if started_at.elapsed() > QR_LOGIN_TIMEOUT {
+            return Err(AuthorizeError::QrLoginTimeout);
         }
-        Err(err) => panic!("Error while sign in: {err}"),
+
+        let response = match migrated_to {
+            Some((dc_id, ref token)) => {
+                trace!(dc_id, "Import login token");
+
+                client
+                    .invoke(&tl::functions::auth::ImportLoginToken {
+                        token: token.clone(),
+                    })
+                    .await
+            }
+            None => {
+                trace!("Export login token");
+
+                client
+                    .invoke(&tl::functions::auth::ExportLoginToken {
+                        api_id: config.api_id,
+                        api_hash: config.api_hash.clone(),
+                        except_ids: vec![],
+                    })
+                    .await
+            }
+        };
+
+        match response.map(QrLoginStep::from) {
+            Ok(QrLoginStep::Show(token)) => {
+                migrated_to = None;
+                if shown_token.as_ref() != Some(&token) {
+                    print_login_qr_code(&token);
+                    shown_token = Some(token);
+                }
+
+                sleep(QR_LOGIN_POLL_INTERVAL).await;
+            }
+            Ok(QrLoginStep::Migrate { dc_id, .. }) if migrated_to.is_some() => {
+                return Err(AuthorizeError::QrLoginMigrate { dc_id });
+            }
+            Ok(QrLoginStep::Migrate { dc_id, token }) => {
+                info!(dc_id, "Switch to the DC of the account to finish QR login");
+
+                client = switch_dc(&client, config, session_file, dc_id)
+                    .await
+                    .map_err(AuthorizeError::SwitchDc)?;
+                migrated_to = Some((dc_id, token));
+            }
+            Ok(QrLoginStep::Done { user_id }) => {
+                if let (Some((dc_id, _)), Some(user_id)) = (migrated_to, user_id) {
+                    client.session().set_user(user_id, dc_id, false);
+                }
+
+                return Ok(client);
+            }
+            Err(InvocationError::Rpc(RpcError { ref name, .. }))
+                if name == "SESSION_PASSWORD_NEEDED" =>
+            {
+                debug!("Request password token");
+
+                let token = get_password_token(&client)
+                    .await
+                    .map_err(AuthorizeError::Request)?;
+                check_password(&client, config, token).await?;
+
+                return Ok(client);
+            }
+            Err(err) => return Err(AuthorizeError::from_request(err)),
+        };
+    }
+}
+
+#[instrument(skip_all)]
+/// Returns the authorized client, it's reconnected if the account is served by another DC
+pub async fn authorize(
+    client: Client,
+    config: &ClientConfig,
+    session_file: &SessionFile,
+) -> Result<Client, AuthorizeError> {
+    debug!("Check authorization info");
+
+    if client
+        .is_authorized()
+        .await
+        .map_err(AuthorizeError::from_request)?
+    {
+        return Ok(client);
+    }
+
+    let client = if let Some(phone_number) = config.phone_number() {
+        sign_in_by_code(&client, config, phone_number).await?;
+        client
+    } else {
+        info!("Phone number is unset, sign in by QR code");
+
+        sign_in_by_qr(client, config, session_file).await?
     };
 
-    sign_out_disconnect_on_err(
-        client,
-        client.session().save_to_file(config.session_file_path()),
-    )
-    .await;
+    // The account is already authorized at this point, so a session that can't be saved
+    // would leave an authorization that nobody is able to reuse
+    sign_out_disconnect_on_err(&client, session_file.save(client.session()))
+        .await
+        .map_err(AuthorizeError::SaveSession)?;
+
+    Ok(client)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn qr_login_is_migrated_to_the_dc_of_the_account() {
+        let step = QrLoginStep::from(enums::auth::LoginToken::MigrateTo(
+            tl_types::auth::LoginTokenMigrateTo {
+                dc_id: 4,
+                token: vec![1, 2, 3],
+            },
+        ));
+
+        assert_eq!(
+            step,
+            QrLoginStep::Migrate {
+                dc_id: 4,
+                token: vec![1, 2, 3],
+            },
+        );
+    }
+
+    #[test]
+    fn imported_token_logs_in_the_user() {
+        let step = QrLoginStep::from(enums::auth::LoginToken::Success(
+            tl_types::auth::LoginTokenSuccess {
+                authorization: enums::auth::Authorization::Authorization(
+                    tl_types::auth::Authorization {
+                        setup_password_required: false,
+                        otherwise_relogin_days: None,
+                        tmp_sessions: None,
+                        future_auth_token: None,
+                        user: enums::User::Empty(tl_types::UserEmpty { id: 42 }),
+                    },
+                ),
+            },
+        ));
+
+        assert_eq!(step, QrLoginStep::Done { user_id: Some(42) });
+    }
 }
@@ -0,0 +1,483 @@
+use std::collections::HashSet;
+
+use grammers_client::{client::bots::InvocationError, Client};
+use grammers_session::{PackedChat, PackedType};
+use grammers_tl_types::{self as tl, enums, types};
+use serde::{Deserialize, Serialize};
+use tracing::{instrument, trace};
+
+use super::api::{Dialog, TelegramApi};
+
+/// ID of the archive folder. The main list is `0`.
+pub const ARCHIVE_FOLDER_ID: i32 = 1;
+
+/// ID of the first custom folder, lower ones are reserved for the main list and the archive
+const MIN_CUSTOM_FOLDER_ID: i32 = 2;
+
+/// Max chats count of a folder for accounts without Telegram Premium
+pub const FOLDER_CHATS_LIMIT: usize = 100;
+
+/// Max dialogs count per request
+const DIALOGS_LIMIT: i32 = 100;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+enum PeerId {
+    Chat(i64),
+    Channel(i64),
+}
+
+impl PeerId {
+    fn from_input_peer(peer: &enums::InputPeer) -> Option<Self> {
+        match peer {
+            enums::InputPeer::Chat(types::InputPeerChat { chat_id }) => {
+                Some(PeerId::Chat(*chat_id))
+            }
+            enums::InputPeer::Channel(types::InputPeerChannel { channel_id, .. }) => {
+                Some(PeerId::Channel(*channel_id))
+            }
+            enums::InputPeer::ChannelFromMessage(peer) => Some(PeerId::Channel(peer.channel_id)),
+            _ => None,
+        }
+    }
+
+    const fn from_packed(ty: PackedType, id: i64) -> Self {
+        match ty {
+            PackedType::Chat | PackedType::User | PackedType::Bot => PeerId::Chat(id),
+            PackedType::Megagroup | PackedType::Broadcast | PackedType::Gigagroup => {
+                PeerId::Channel(id)
+            }
+        }
+    }
+
+    const fn from_chat(chat: &enums::Chat) -> Self {
+        match chat {
+            enums::Chat::Empty(chat) => PeerId::Chat(chat.id),
+            enums::Chat::Chat(chat) => PeerId::Chat(chat.id),
+            enums::Chat::Forbidden(chat) => PeerId::Chat(chat.id),
+            enums::Chat::Channel(channel) => PeerId::Channel(channel.id),
+            enums::Chat::ChannelForbidden(channel) => PeerId::Channel(channel.id),
+        }
+    }
+
+    fn map_id(self, f: &mut impl FnMut(i64) -> i64) -> Self {
+        match self {
+            PeerId::Chat(id) => PeerId::Chat(f(id)),
+            PeerId::Channel(id) => PeerId::Channel(f(id)),
+        }
+    }
+}
+
+/// Folder (dialog filter) of the account. Only rules for groups and channels are kept, since
+/// other chats aren't analyzed.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Folder {
+    pub title: Box<str>,
+    include_peers: HashSet<PeerId>,
+    exclude_peers: HashSet<PeerId>,
+    groups: bool,
+    broadcasts: bool,
+    exclude_muted: bool,
+    exclude_read: bool,
+    exclude_archived: bool,
+    /// Users and bots aren't analyzed, so it's only known whether the folder has any of them
+    #[serde(default)]
+    other_chats: bool,
+}
+
+impl Folder {
+    fn from_raw(filter: enums::DialogFilter) -> Option<Self> {
+        let peers = |peers: &[enums::InputPeer]| {
+            peers
+                .iter()
+                .filter_map(PeerId::from_input_peer)
+                .collect::<HashSet<_>>()
+        };
+        let has_users = |peers: &[enums::InputPeer]| {
+            peers
+                .iter()
+                .any(|peer| PeerId::from_input_peer(peer).is_none())
+        };
+
+        match filter {
+            enums::DialogFilter::Filter(filter) => Some(Folder {
+                title: filter.title.into(),
+                include_peers: peers(&filter.pinned_peers)
+                    .into_iter()
+                    .chain(peers(&filter.include_peers))
+                    .collect(),
+                exclude_peers: peers(&filter.exclude_peers),
+                groups: filter.groups,
+                broadcasts: filter.broadcasts,
+                exclude_muted: filter.exclude_muted,
+                exclude_read: filter.exclude_read,
+                exclude_archived: filter.exclude_archived,
+                other_chats: filter.contacts
+                    || filter.non_contacts
+                    || filter.bots
+                    || has_users(&filter.pinned_peers)
+                    || has_users(&filter.include_peers),
+            }),
+            enums::DialogFilter::Chatlist(filter) => Some(Folder {
+                title: filter.title.into(),
+                include_peers: peers(&filter.pinned_peers)
+                    .into_iter()
+                    .chain(peers(&filter.include_peers))
+                    .collect(),
+                exclude_peers: HashSet::new(),
+                groups: false,
+                broadcasts: false,
+                exclude_muted: false,
+                exclude_read: false,
+                exclude_archived: false,
+                other_chats: has_users(&filter.pinned_peers) || has_users(&filter.include_peers),
+            }),
+            // "All chats" folder has no ID to filter by
+            enums::DialogFilter::Default => None,
+        }
+    }
+
+    /// Folder with exactly these chats
+    #[cfg(test)]
+    pub fn with_chats(title: &str, chats: &[PackedChat]) -> Self {
+        Folder {
+            title: title.into(),
+            include_peers: chats
+                .iter()
+                .map(|chat| PeerId::from_packed(chat.ty, chat.id))
+                .collect(),
+            exclude_peers: HashSet::new(),
+            groups: false,
+            broadcasts: false,
+            exclude_muted: false,
+            exclude_read: false,
+            exclude_archived: false,
+            other_chats: false,
+        }
+    }
+
+    /// Folder with the chat IDs replaced, e.g. to anonymize them
+    pub fn map_ids(self, mut f: impl FnMut(i64) -> i64) -> Self {
+        Folder {
+            include_peers: self
+                .include_peers
+                .into_iter()
+                .map(|peer| peer.map_id(&mut f))
+                .collect(),
+            exclude_peers: self
+                .exclude_peers
+                .into_iter()
+                .map(|peer| peer.map_id(&mut f))
+                .collect(),
+            ..self
+        }
+    }
+
+    /// Folder has no rules and only chats among these ones, so nothing is lost by replacing its
+    /// chats with them
+    pub fn is_subset_of(&self, chats: &[PackedChat]) -> bool {
+        let chats = chats
+            .iter()
+            .map(|chat| PeerId::from_packed(chat.ty, chat.id))
+            .collect::<HashSet<_>>();
+
+        !(self.other_chats || self.groups || self.broadcasts || !self.exclude_peers.is_empty())
+            && self.include_peers.is_subset(&chats)
+    }
+
+    /// Explicitly included chats are always in the folder, other ones by the folder rules
+    pub fn contains(&self, dialog: &Dialog) -> bool {
+        let ty = dialog.chat.ty;
+        let peer = PeerId::from_packed(ty, dialog.chat.id);
+        if self.include_peers.contains(&peer) {
+            return true;
+        }
+        if self.exclude_peers.contains(&peer) {
+            return false;
+        }
+
+        let included_by_kind = match ty {
+            PackedType::Chat | PackedType::Megagroup | PackedType::Gigagroup => self.groups,
+            PackedType::Broadcast => self.broadcasts,
+            PackedType::User | PackedType::Bot => false,
+        };
+
+        included_by_kind
+            && !(self.exclude_archived && dialog.folder_id == Some(ARCHIVE_FOLDER_ID)
+                || self.exclude_read && dialog.unread_count == 0 && !dialog.unread_mark
+                || self.exclude_muted && dialog.muted)
+    }
+}
+
+async fn get_raw_folders(client: &Client) -> Result<Vec<enums::DialogFilter>, InvocationError> {
+    let enums::messages::DialogFilters::Filters(types::messages::DialogFilters { filters, .. }) =
+        client
+            .invoke(&tl::functions::messages::GetDialogFilters {})
+            .await?;
+
+    Ok(filters)
+}
+
+#[instrument(skip_all)]
+pub async fn get_folders(client: &Client) -> Result<Vec<Folder>, InvocationError> {
+    Ok(get_raw_folders(client)
+        .await?
+        .into_iter()
+        .filter_map(Folder::from_raw)
+        .collect())
+}
+
+/// Create the folder with exactly these chats or replace chats of the existing folder with the
+/// same title, compared case-insensitively. Title, icon and color of the existing folder are
+/// kept, as well as sharing of the shared one. Returns `true` if it's created.
+#[instrument(skip(client, chats), fields(chats_count = chats.len()))]
+pub async fn save_folder(
+    client: &Client,
+    title: &str,
+    chats: &[PackedChat],
+) -> Result<bool, InvocationError> {
+    let raw_folders = get_raw_folders(client).await?;
+
+    let lowercase_title = title.to_lowercase();
+    let existing = raw_folders.iter().find(|folder| match folder {
+        enums::DialogFilter::Filter(folder) => folder.title.to_lowercase() == lowercase_title,
+        enums::DialogFilter::Chatlist(folder) => folder.title.to_lowercase() == lowercase_title,
+        enums::DialogFilter::Default => false,
+    });
+    let created = existing.is_none();
+
+    let include_peers = chats.iter().map(PackedChat::to_input_peer).collect();
+    let (id, folder) = match existing {
+        Some(enums::DialogFilter::Chatlist(folder)) => (
+            folder.id,
+            enums::DialogFilter::Chatlist(types::DialogFilterChatlist {
+                pinned_peers: vec![],
+                include_peers,
+                ..folder.clone()
+            }),
+        ),
+        Some(enums::DialogFilter::Filter(folder)) => (
+            folder.id,
+            enums::DialogFilter::Filter(types::DialogFilter {
+                contacts: false,
+                non_contacts: false,
+                groups: false,
+                broadcasts: false,
+                bots: false,
+                exclude_muted: false,
+                exclude_read: false,
+                exclude_archived: false,
+                pinned_peers: vec![],
+                include_peers,
+                exclude_peers: vec![],
+                ..folder.clone()
+            }),
+        ),
+        Some(enums::DialogFilter::Default) | None => {
+            let id = raw_folders
+                .iter()
+                .filter_map(|folder| match folder {
+                    enums::DialogFilter::Filter(folder) => Some(folder.id),
+                    enums::DialogFilter::Chatlist(folder) => Some(folder.id),
+                    enums::DialogFilter::Default => None,
+                })
+                .max()
+                .map_or(MIN_CUSTOM_FOLDER_ID, |id| {
+                    (id + 1).max(MIN_CUSTOM_FOLDER_ID)
+                });
+
+            (
+                id,
+                enums::DialogFilter::Filter(types::DialogFilter {
+                    contacts: false,
+                    non_contacts: false,
+                    groups: false,
+                    broadcasts: false,
+                    bots: false,
+                    exclude_muted: false,
+                    exclude_read: false,
+                    exclude_archived: false,
+                    id,
+                    title: title.to_owned(),
+                    emoticon: None,
+                    color: None,
+                    pinned_peers: vec![],
+                    include_peers,
+                    exclude_peers: vec![],
+                }),
+            )
+        }
+    };
+
+    client
+        .invoke(&tl::functions::messages::UpdateDialogFilter {
+            id,
+            filter: Some(folder),
+        })
+        .await?;
+
+    Ok(created)
+}
+
+/// Folder is found by its title case-insensitively. Titles of all folders are returned as the
+/// error if it's not found.
+pub async fn find_folder(
+    api: &impl TelegramApi,
+    title: &str,
+) -> Result<Result<Folder, Box<[Box<str>]>>, InvocationError> {
+    let mut folders = api.get_folders().await?;
+    let title = title.to_lowercase();
+
+    Ok(
+        match folders
+            .iter()
+            .position(|folder| folder.title.to_lowercase() == title)
+        {
+            Some(index) => Ok(folders.swap_remove(index)),
+            None => Err(folders.into_iter().map(|folder| folder.title).collect()),
+        },
+    )
+}
+
+/// Input peer of the dialog to continue the iteration from
+fn offset_peer(
+    peer: &enums::Peer,
+    chats: &[enums::Chat],
+    users: &[enums::User],
+) -> Option<enums::InputPeer> {
+    match peer {
+        enums::Peer::Chat(peer) => Some(enums::InputPeer::Chat(types::InputPeerChat {
+            chat_id: peer.chat_id,
+        })),
+        enums::Peer::Channel(peer) => chats.iter().find_map(|chat| match chat {
+            enums::Chat::Channel(channel) if channel.id == peer.channel_id => {
+                Some(enums::InputPeer::Channel(types::InputPeerChannel {
+                    channel_id: channel.id,
+                    access_hash: channel.access_hash?,
+                }))
+            }
+            enums::Chat::ChannelForbidden(channel) if channel.id == peer.channel_id => {
+                Some(enums::InputPeer::Channel(types::InputPeerChannel {
+                    channel_id: channel.id,
+                    access_hash: channel.access_hash,
+                }))
+            }
+            _ => None,
+        }),
+        enums::Peer::User(peer) => users.iter().find_map(|user| match user {
+            enums::User::User(user) if user.id == peer.user_id => {
+                Some(enums::InputPeer::User(types::InputPeerUser {
+                    user_id: user.id,
+                    access_hash: user.access_hash?,
+                }))
+            }
+            _ => None,
+        }),
+    }
+}
+
+fn top_message<'a>(
+    dialog: &types::Dialog,
+    messages: &'a [enums::Message],
+) -> Option<&'a enums::Message> {
+    messages.iter().find(|message| match message {
+        enums::Message::Message(message) => {
+            message.id == dialog.top_message && message.peer_id == dialog.peer
+        }
+        enums::Message::Service(message) => {
+            message.id == dialog.top_message && message.peer_id == dialog.peer
+        }
+        enums::Message::Empty(_) => false,
+    })
+}
+
+/// Date of the dialog top message to continue the iteration from
+fn offset_date(dialog: &types::Dialog, messages: &[enums::Message]) -> i32 {
+    match top_message(dialog, messages) {
+        Some(enums::Message::Message(message)) => message.date,
+        Some(enums::Message::Service(message)) => message.date,
+        _ => 0,
+    }
+}
+
+/// Groups and channels of the peer folder, e.g. [`ARCHIVE_FOLDER_ID`]. `client.iter_dialogs()`
+/// walks only the main list.
+#[instrument(skip(client))]
+pub async fn get_folder_dialogs(
+    client: &Client,
+    folder_id: i32,
+) -> Result<Vec<Dialog>, InvocationError> {
+    let mut folder_dialogs = vec![];
+    let mut request = tl::functions::messages::GetDialogs {
+        exclude_pinned: false,
+        folder_id: Some(folder_id),
+        offset_date: 0,
+        offset_id: 0,
+        offset_peer: enums::InputPeer::Empty,
+        limit: DIALOGS_LIMIT,
+        hash: 0,
+    };
+
+    loop {
+        let (dialogs, messages, chats, users, total) = match client.invoke(&request).await? {
+            enums::messages::Dialogs::Dialogs(types::messages::Dialogs {
+                dialogs,
+                messages,
+                chats,
+                users,
+            }) => (dialogs, messages, chats, users, None),
+            enums::messages::Dialogs::Slice(types::messages::DialogsSlice {
+                count,
+                dialogs,
+                messages,
+                chats,
+                users,
+            }) => (dialogs, messages, chats, users, Some(count)),
+            enums::messages::Dialogs::NotModified(_) => break,
+        };
+        let received = dialogs.len();
+
+        let mut last_dialog = None;
+        for dialog in dialogs {
+            let enums::Dialog::Dialog(dialog) = dialog else {
+                continue;
+            };
+
+            let peer = match dialog.peer {
+                enums::Peer::Chat(ref peer) => Some(PeerId::Chat(peer.chat_id)),
+                enums::Peer::Channel(ref peer) => Some(PeerId::Channel(peer.channel_id)),
+                enums::Peer::User(_) => None,
+            };
+            if let Some(chat) =
+                peer.and_then(|peer| chats.iter().find(|chat| PeerId::from_chat(chat) == peer))
+            {
+                folder_dialogs.extend(Dialog::from_raw(
+                    &dialog,
+                    chat,
+                    top_message(&dialog, &messages),
+                    &users,
+                ));
+            }
+
+            last_dialog = Some(dialog);
+        }
+
+        trace!(received, total, "Got folder dialogs");
+
+        // Without the total count all dialogs are already received
+        let Some(last_dialog) = last_dialog else {
+            break;
+        };
+        if total.is_none() || received < DIALOGS_LIMIT as usize {
+            break;
+        }
+        let Some(offset_peer) = offset_peer(&last_dialog.peer, &chats, &users) else {
+            break;
+        };
+
+        request.offset_date = offset_date(&last_dialog, &messages);
+        request.offset_id = last_dialog.top_message;
+        request.offset_peer = offset_peer;
+    }
+
+    Ok(folder_dialogs)
+}
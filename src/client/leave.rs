@@ -0,0 +1,34 @@
+use grammers_client::{client::bots::InvocationError, Client};
+use grammers_tl_types::{self as tl, enums, types};
+use tracing::instrument;
+
+#[instrument(skip_all, fields(id, access_hash))]
+pub async fn leave_channel(
+    client: &Client,
+    id: i64,
+    access_hash: Option<i64>,
+) -> Result<(), InvocationError> {
+    client
+        .invoke(&tl::functions::channels::LeaveChannel {
+            channel: enums::InputChannel::Channel(types::InputChannel {
+                channel_id: id,
+                access_hash: access_hash.unwrap_or(0),
+            }),
+        })
+        .await?;
+
+    Ok(())
+}
+
+#[instrument(skip_all, fields(id))]
+pub async fn leave_group(client: &Client, id: i64) -> Result<(), InvocationError> {
+    client
+        .invoke(&tl::functions::messages::DeleteChatUser {
+            revoke_history: false,
+            chat_id: id,
+            user_id: enums::InputUser::UserSelf,
+        })
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,150 @@
+use std::collections::HashSet;
+
+use grammers_client::{client::bots::InvocationError, Client};
+use grammers_mtsender::RpcError;
+use grammers_session::{PackedChat, PackedType};
+use grammers_tl_types::{self as tl, enums, types};
+use tracing::instrument;
+
+use super::api::Members;
+
+/// Admins are listed by a single request, chats with more admins than that aren't ghost anyway
+const ADMINS_LIMIT: i32 = 100;
+
+fn bot_ids(users: &[enums::User]) -> HashSet<i64> {
+    users
+        .iter()
+        .filter_map(|user| match user {
+            enums::User::User(user) if user.bot => Some(user.id),
+            _ => None,
+        })
+        .collect()
+}
+
+async fn get_group_members(client: &Client, chat_id: i64) -> Result<Members, InvocationError> {
+    let enums::messages::ChatFull::Full(types::messages::ChatFull {
+        full_chat, users, ..
+    }) = client
+        .invoke(&tl::functions::messages::GetFullChat { chat_id })
+        .await?;
+
+    // Members are hidden if you aren't a member anymore
+    let enums::ChatFull::Full(types::ChatFull {
+        participants:
+            enums::ChatParticipants::Participants(types::ChatParticipants { participants, .. }),
+        ..
+    }) = full_chat
+    else {
+        return Ok(Members::default());
+    };
+
+    let bots = bot_ids(&users);
+    let user_id = |participant: &enums::ChatParticipant| match participant {
+        enums::ChatParticipant::Participant(types::ChatParticipant { user_id, .. })
+        | enums::ChatParticipant::Creator(types::ChatParticipantCreator { user_id })
+        | enums::ChatParticipant::Admin(types::ChatParticipantAdmin { user_id, .. }) => *user_id,
+    };
+
+    Ok(Members {
+        count: participants.len().try_into().ok(),
+        bots: participants
+            .iter()
+            .filter(|participant| bots.contains(&user_id(participant)))
+            .count(),
+        admins: Some(
+            participants
+                .iter()
+                .filter(|participant| {
+                    !matches!(participant, enums::ChatParticipant::Participant(_))
+                })
+                .filter(|participant| !bots.contains(&user_id(participant)))
+                .count(),
+        ),
+    })
+}
+
+async fn get_channel_members(
+    client: &Client,
+    id: i64,
+    access_hash: Option<i64>,
+) -> Result<Members, InvocationError> {
+    let channel = enums::InputChannel::Channel(types::InputChannel {
+        channel_id: id,
+        access_hash: access_hash.unwrap_or(0),
+    });
+
+    let enums::messages::ChatFull::Full(types::messages::ChatFull { full_chat, .. }) = client
+        .invoke(&tl::functions::channels::GetFullChannel {
+            channel: channel.clone(),
+        })
+        .await?;
+    let (count, bots) = match full_chat {
+        enums::ChatFull::ChannelFull(types::ChannelFull {
+            participants_count,
+            bot_info,
+            ..
+        }) => (participants_count, bot_info.len()),
+        enums::ChatFull::Full(_) => (None, 0),
+    };
+
+    let admins = match client
+        .invoke(&tl::functions::channels::GetParticipants {
+            channel,
+            filter: enums::ChannelParticipantsFilter::ChannelParticipantsAdmins,
+            offset: 0,
+            limit: ADMINS_LIMIT,
+            hash: 0,
+        })
+        .await
+    {
+        Ok(enums::channels::ChannelParticipants::Participants(
+            types::channels::ChannelParticipants {
+                participants,
+                users,
+                ..
+            },
+        )) => {
+            let bots = bot_ids(&users);
+
+            Some(
+                participants
+                    .iter()
+                    .filter_map(|participant| match participant {
+                        enums::ChannelParticipant::Creator(types::ChannelParticipantCreator {
+                            user_id,
+                            ..
+                        })
+                        | enums::ChannelParticipant::Admin(types::ChannelParticipantAdmin {
+                            user_id,
+                            ..
+                        }) => Some(*user_id),
+                        _ => None,
+                    })
+                    .filter(|user_id| !bots.contains(user_id))
+                    .count(),
+            )
+        }
+        Ok(enums::channels::ChannelParticipants::NotModified) => None,
+        // Admins are hidden from subscribers of channels
+        Err(InvocationError::Rpc(RpcError {
+            code: 400 | 403, ..
+        })) => None,
+        Err(err) => return Err(err),
+    };
+
+    Ok(Members {
+        count,
+        bots,
+        admins,
+    })
+}
+
+/// Members of the chat by `messages.getFullChat` for groups, and `channels.getFullChannel` with
+/// the list of admins for channels
+#[instrument(skip_all, fields(id = chat.id))]
+pub async fn get_members(client: &Client, chat: PackedChat) -> Result<Members, InvocationError> {
+    match chat.ty {
+        PackedType::Chat => get_group_members(client, chat.id).await,
+        _ => get_channel_members(client, chat.id, chat.access_hash).await,
+    }
+}
@@ -0,0 +1,29 @@
+use grammers_client::{client::bots::InvocationError, Client};
+use grammers_session::PackedChat;
+use grammers_tl_types::{self as tl, enums, types};
+use tracing::instrument;
+
+/// Max date of the mute, Telegram treats it as forever
+const MUTE_FOREVER: i32 = i32::MAX;
+
+#[instrument(skip_all, fields(id = chat.id))]
+pub async fn mute_chat(client: &Client, chat: PackedChat) -> Result<(), InvocationError> {
+    client
+        .invoke(&tl::functions::account::UpdateNotifySettings {
+            peer: enums::InputNotifyPeer::Peer(types::InputNotifyPeer {
+                peer: chat.to_input_peer(),
+            }),
+            settings: enums::InputPeerNotifySettings::Settings(types::InputPeerNotifySettings {
+                show_previews: None,
+                silent: None,
+                mute_until: Some(MUTE_FOREVER),
+                sound: None,
+                stories_muted: None,
+                stories_hide_sender: None,
+                stories_sound: None,
+            }),
+        })
+        .await?;
+
+    Ok(())
+}
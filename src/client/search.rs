@@ -0,0 +1,92 @@
+use grammers_client::{client::bots::InvocationError, Client};
+use grammers_session::PackedChat;
+use grammers_tl_types::{self as tl, enums, types};
+use tracing::instrument;
+
+/// Your messages in the chat, the latest first. The search has no query, since it's filtered by
+/// the sender.
+#[instrument(skip_all, fields(id = chat.id))]
+pub async fn search_own_messages(
+    client: &Client,
+    chat: PackedChat,
+    limit: i32,
+) -> Result<Vec<enums::Message>, InvocationError> {
+    let found = client
+        .invoke(&tl::functions::messages::Search {
+            peer: chat.to_input_peer(),
+            q: String::new(),
+            from_id: Some(enums::InputPeer::PeerSelf),
+            saved_peer_id: None,
+            saved_reaction: None,
+            top_msg_id: None,
+            filter: enums::MessagesFilter::InputMessagesFilterEmpty,
+            min_date: 0,
+            max_date: 0,
+            offset_id: 0,
+            add_offset: 0,
+            limit,
+            max_id: 0,
+            min_id: 0,
+            hash: 0,
+        })
+        .await?;
+
+    Ok(match found {
+        enums::messages::Messages::Messages(types::messages::Messages { messages, .. })
+        | enums::messages::Messages::Slice(types::messages::MessagesSlice { messages, .. })
+        | enums::messages::Messages::ChannelMessages(types::messages::ChannelMessages {
+            messages,
+            ..
+        }) => messages,
+        enums::messages::Messages::NotModified(_) => vec![],
+    })
+}
+
+/// Number of messages of the chat sent since the date. The history is requested from the date
+/// with a single message, and the response tells how many messages are newer than it.
+#[instrument(skip_all, fields(id = chat.id))]
+pub async fn count_messages_since(
+    client: &Client,
+    chat: PackedChat,
+    date: i32,
+) -> Result<usize, InvocationError> {
+    let history = client
+        .invoke(&tl::functions::messages::GetHistory {
+            peer: chat.to_input_peer(),
+            offset_id: 0,
+            offset_date: date,
+            add_offset: 0,
+            limit: 1,
+            max_id: 0,
+            min_id: 0,
+            hash: 0,
+        })
+        .await?;
+
+    Ok(match history {
+        enums::messages::Messages::Slice(types::messages::MessagesSlice {
+            offset_id_offset,
+            ..
+        })
+        | enums::messages::Messages::ChannelMessages(types::messages::ChannelMessages {
+            offset_id_offset,
+            ..
+        }) => offset_id_offset
+            .and_then(|offset| offset.try_into().ok())
+            .unwrap_or_default(),
+        // The response isn't sliced, so it has no offset and its own messages are counted
+        enums::messages::Messages::Messages(types::messages::Messages { messages, .. }) => messages
+            .iter()
+            .filter(|message| message_date(message).is_some_and(|sent| sent >= date))
+            .count(),
+        enums::messages::Messages::NotModified(_) => 0,
+    })
+}
+
+fn message_date(message: &enums::Message) -> Option<i32> {
+    match message {
+        enums::Message::Message(message) => Some(message.date),
+        enums::Message::Service(message) => Some(message.date),
+        enums::Message::Empty(_) => None,
+    }
+}
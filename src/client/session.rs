@@ -0,0 +1,39 @@
+use grammers_client::{client::bots::InvocationError, Client};
+use grammers_tl_types::{self as tl, enums, types};
+use tracing::instrument;
+
+/// DC the client is connected to. It's the home DC of the account after authorization.
+#[instrument(skip_all)]
+pub async fn get_current_dc(client: &Client) -> Result<i32, InvocationError> {
+    client
+        .invoke(&tl::functions::help::GetNearestDc {})
+        .await
+        .map(|enums::NearestDc::Dc(types::NearestDc { this_dc, .. })| this_dc)
+}
+
+#[instrument(skip_all)]
+pub async fn get_authorizations(
+    client: &Client,
+) -> Result<Vec<types::Authorization>, InvocationError> {
+    client
+        .invoke(&tl::functions::account::GetAuthorizations {})
+        .await
+        .map(
+            |enums::account::Authorizations::Authorizations(types::account::Authorizations {
+                 authorizations,
+                 ..
+             })| {
+                authorizations
+                    .into_iter()
+                    .map(|enums::Authorization::Authorization(authorization)| authorization)
+                    .collect()
+            },
+        )
+}
+
+#[instrument(skip_all, fields(hash))]
+pub async fn reset_authorization(client: &Client, hash: i64) -> Result<bool, InvocationError> {
+    client
+        .invoke(&tl::functions::account::ResetAuthorization { hash })
+        .await
+}
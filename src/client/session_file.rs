@@ -0,0 +1,365 @@
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use argon2::Argon2;
+use chacha20poly1305::{
+    aead::{rand_core::RngCore as _, Aead as _, OsRng},
+    AeadCore as _, ChaCha20Poly1305, Key, KeyInit as _, Nonce,
+};
+use grammers_session::Session;
+use tracing::{instrument, trace, warn};
+
+use crate::configs::ClientConfig;
+
+/// Header of encrypted session files. It's followed by the salt, the nonce and the ciphertext.
+const ENCRYPTED_MAGIC: &[u8] = b"TGOCMSE1";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+#[derive(Debug)]
+pub enum SessionError {
+    Io { path: PathBuf, source: io::Error },
+    NotFound { path: PathBuf },
+    Malformed { path: PathBuf },
+    AlreadyEncrypted { path: PathBuf },
+    WrongPassphrase { path: PathBuf },
+    PassphraseInput(io::Error),
+    PassphraseMismatch,
+    KeyDerivation(argon2::Error),
+    Encryption,
+}
+
+impl Display for SessionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            SessionError::Io { path, source } => write!(
+                f,
+                "Can't access session file `{path}`: {source}",
+                path = path.display(),
+            ),
+            SessionError::NotFound { path } => write!(
+                f,
+                "Session file `{path}` not found. Log in first",
+                path = path.display(),
+            ),
+            SessionError::Malformed { path } => write!(
+                f,
+                "Session file `{path}` is malformed. Remove it to log in again",
+                path = path.display(),
+            ),
+            SessionError::AlreadyEncrypted { path } => write!(
+                f,
+                "Session file `{path}` is already encrypted",
+                path = path.display(),
+            ),
+            SessionError::WrongPassphrase { path } => write!(
+                f,
+                "Can't decrypt session file `{path}`: the passphrase is wrong or the file is corrupted",
+                path = path.display(),
+            ),
+            SessionError::PassphraseInput(err) => {
+                write!(f, "Can't read the passphrase of the session file: {err}")
+            }
+            SessionError::PassphraseMismatch => write!(f, "The passphrases don't match"),
+            SessionError::KeyDerivation(err) => {
+                write!(f, "Can't derive the key of the session file: {err}")
+            }
+            SessionError::Encryption => write!(f, "Can't encrypt the session file"),
+        }
+    }
+}
+
+impl Error for SessionError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SessionError::Io { source, .. } | SessionError::PassphraseInput(source) => Some(source),
+            _ => None,
+        }
+    }
+}
+
+fn is_encrypted(data: &[u8]) -> bool {
+    data.starts_with(ENCRYPTED_MAGIC)
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<Key, SessionError> {
+    let mut key = Key::default();
+
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(SessionError::KeyDerivation)?;
+
+    Ok(key)
+}
+
+fn encrypt(data: &[u8], passphrase: &str) -> Result<Vec<u8>, SessionError> {
+    let mut salt = [0; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = ChaCha20Poly1305::new(&derive_key(passphrase, &salt)?)
+        .encrypt(&nonce, data)
+        .map_err(|_| SessionError::Encryption)?;
+
+    let mut encrypted =
+        Vec::with_capacity(ENCRYPTED_MAGIC.len() + SALT_LEN + NONCE_LEN + ciphertext.len());
+    encrypted.extend_from_slice(ENCRYPTED_MAGIC);
+    encrypted.extend_from_slice(&salt);
+    encrypted.extend_from_slice(&nonce);
+    encrypted.extend_from_slice(&ciphertext);
+
+    Ok(encrypted)
+}
+
+fn decrypt(data: &[u8], passphrase: &str, path: &Path) -> Result<Vec<u8>, SessionError> {
+    let data = &data[ENCRYPTED_MAGIC.len()..];
+    if data.len() < SALT_LEN + NONCE_LEN {
+        return Err(SessionError::Malformed {
+            path: path.to_path_buf(),
+        });
+    }
+
+    let (salt, data) = data.split_at(SALT_LEN);
+    let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+
+    ChaCha20Poly1305::new(&derive_key(passphrase, salt)?)
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| SessionError::WrongPassphrase {
+            path: path.to_path_buf(),
+        })
+}
+
+fn prompt_passphrase(prompt: &str) -> Result<Box<str>, SessionError> {
+    rpassword::prompt_password(prompt)
+        .map(Into::into)
+        .map_err(SessionError::PassphraseInput)
+}
+
+/// Passphrase for the file that isn't encrypted yet. A prompted one is asked twice, since a typo
+/// would lock you out of the session.
+fn new_passphrase(config: &ClientConfig) -> Result<Box<str>, SessionError> {
+    if let Some(passphrase) = config.session_passphrase() {
+        return Ok(passphrase.into());
+    }
+
+    let passphrase = prompt_passphrase("Enter a new passphrase of the session file: ")?;
+    if passphrase != prompt_passphrase("Repeat the passphrase: ")? {
+        return Err(SessionError::PassphraseMismatch);
+    }
+
+    Ok(passphrase)
+}
+
+fn read(path: &Path) -> Result<Option<Vec<u8>>, SessionError> {
+    match fs::read(path) {
+        Ok(data) => Ok(Some(data)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(source) => Err(SessionError::Io {
+            path: path.to_path_buf(),
+            source,
+        }),
+    }
+}
+
+/// Write to the temporary file first to not lose the session if the write fails. Missing
+/// directories are created, since the default path is in the data directory.
+fn write(path: &Path, data: &[u8]) -> Result<(), SessionError> {
+    let tmp_path = path.with_extension("tmp");
+
+    match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => fs::create_dir_all(dir),
+        _ => Ok(()),
+    }
+    .and_then(|()| fs::write(&tmp_path, data))
+    .and_then(|()| fs::rename(&tmp_path, path))
+    .map_err(|source| SessionError::Io {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+pub fn is_file_encrypted(path: &Path) -> Result<bool, SessionError> {
+    Ok(read(path)?.is_some_and(|data| is_encrypted(&data)))
+}
+
+pub fn remove(path: &Path) -> Result<(), SessionError> {
+    match fs::remove_file(path) {
+        Ok(()) => Ok(()),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        Err(source) => Err(SessionError::Io {
+            path: path.to_path_buf(),
+            source,
+        }),
+    }
+}
+
+/// Session file which is transparently encrypted with a passphrase if it's required
+pub struct SessionFile {
+    path: PathBuf,
+    passphrase: Option<Box<str>>,
+}
+
+impl SessionFile {
+    /// The passphrase is required if the file is already encrypted or a new file is created with
+    /// encryption enabled in the config. It's taken from the config or prompted. An existing
+    /// plaintext file is kept as is until `session encrypt` command encrypts it.
+    #[instrument(skip_all)]
+    pub fn open(config: &ClientConfig) -> Result<Self, SessionError> {
+        let path = config.session_file_path().to_path_buf();
+
+        let passphrase = if is_file_encrypted(&path)? {
+            match config.session_passphrase() {
+                Some(passphrase) => Some(passphrase.into()),
+                None => Some(prompt_passphrase(
+                    "Enter the passphrase of the session file: ",
+                )?),
+            }
+        } else if !config.encrypt_session() {
+            None
+        } else if path.exists() {
+            warn!(
+                path = %path.display(),
+                "Session file isn't encrypted. Run `session encrypt` command to encrypt it"
+            );
+
+            None
+        } else {
+            Some(new_passphrase(config)?)
+        };
+
+        Ok(Self { path, passphrase })
+    }
+
+    /// Decrypted content of the file, `None` if it doesn't exist
+    fn read_data(&self) -> Result<Option<Vec<u8>>, SessionError> {
+        let Some(data) = read(&self.path)? else {
+            return Ok(None);
+        };
+
+        match (is_encrypted(&data), self.passphrase.as_deref()) {
+            (true, Some(passphrase)) => decrypt(&data, passphrase, &self.path).map(Some),
+            (true, None) => Err(SessionError::WrongPassphrase {
+                path: self.path.clone(),
+            }),
+            (false, _) => Ok(Some(data)),
+        }
+    }
+
+    fn write_data(&self, data: &[u8]) -> Result<(), SessionError> {
+        match self.passphrase.as_deref() {
+            Some(passphrase) => write(&self.path, &encrypt(data, passphrase)?),
+            None => write(&self.path, data),
+        }
+    }
+
+    #[instrument(skip_all)]
+    pub fn load_or_create(&self) -> Result<Session, SessionError> {
+        let Some(data) = self.read_data()? else {
+            trace!("Session file not found, create new session");
+
+            return Ok(Session::new());
+        };
+
+        Session::load(&data).map_err(|_| SessionError::Malformed {
+            path: self.path.clone(),
+        })
+    }
+
+    #[instrument(skip_all)]
+    pub fn save(&self, session: &Session) -> Result<(), SessionError> {
+        self.write_data(&session.save())
+    }
+}
+
+/// Encrypt the existing plaintext session file
+#[instrument(skip_all)]
+pub fn encrypt_existing(config: &ClientConfig) -> Result<(), SessionError> {
+    let path = config.session_file_path();
+
+    let data = read(path)?.ok_or_else(|| SessionError::NotFound {
+        path: path.to_path_buf(),
+    })?;
+    if is_encrypted(&data) {
+        return Err(SessionError::AlreadyEncrypted {
+            path: path.to_path_buf(),
+        });
+    }
+
+    write(path, &encrypt(&data, &new_passphrase(config)?)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process};
+
+    use super::*;
+
+    const DATA: &[u8] = b"session data";
+
+    fn temp_path(name: &str) -> PathBuf {
+        env::temp_dir().join(format!(
+            "tg_old_chats_manager_{name}_{pid}.session",
+            pid = process::id(),
+        ))
+    }
+
+    #[test]
+    fn encrypted_data_is_decrypted_with_the_passphrase() {
+        let encrypted = encrypt(DATA, "passphrase").unwrap();
+
+        assert!(is_encrypted(&encrypted));
+        assert!(!encrypted.windows(DATA.len()).any(|window| window == DATA));
+        assert_eq!(
+            decrypt(&encrypted, "passphrase", Path::new("client.session")).unwrap(),
+            DATA,
+        );
+    }
+
+    #[test]
+    fn wrong_passphrase_and_truncated_file_are_rejected() {
+        let encrypted = encrypt(DATA, "passphrase").unwrap();
+        let path = Path::new("client.session");
+
+        assert!(matches!(
+            decrypt(&encrypted, "wrong", path),
+            Err(SessionError::WrongPassphrase { .. }),
+        ));
+        assert!(matches!(
+            decrypt(
+                &encrypted[..ENCRYPTED_MAGIC.len() + SALT_LEN],
+                "passphrase",
+                path
+            ),
+            Err(SessionError::Malformed { .. }),
+        ));
+    }
+
+    #[test]
+    fn session_file_is_written_and_read_back() {
+        let path = temp_path("round_trip");
+        let encrypted = SessionFile {
+            path: path.clone(),
+            passphrase: Some("passphrase".into()),
+        };
+
+        encrypted.write_data(DATA).unwrap();
+        assert!(is_file_encrypted(&path).unwrap());
+        assert_eq!(encrypted.read_data().unwrap().as_deref(), Some(DATA));
+
+        let without_passphrase = SessionFile {
+            path: path.clone(),
+            passphrase: None,
+        };
+        assert!(matches!(
+            without_passphrase.read_data(),
+            Err(SessionError::WrongPassphrase { .. }),
+        ));
+
+        remove(&path).unwrap();
+        assert_eq!(encrypted.read_data().unwrap(), None);
+    }
+}
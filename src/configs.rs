@@ -1,4 +1,15 @@
+mod check;
 mod client;
+mod ignore;
+mod overrides;
+mod paths;
+mod template;
 
-#[allow(unused_imports)]
-pub use client::{read_raw_toml, Client as ClientConfig, Config, Logging as LoggingConfig};
+pub use check::{check, check_api_hash, check_api_id, check_phone_number, check_session_file_path};
+pub use client::{
+    read_raw_toml, Client as ClientConfig, Config, Profile, ProfileError, DEFAULT_PROFILE_NAME,
+};
+pub use ignore::{IgnoreError, IgnoreList, IgnoreMatcher};
+pub use overrides::{read_secret_file, Overrides, OverridesError};
+pub use paths::{default_session_file_path, find_config_file, new_config_file_path};
+pub use template::Template;
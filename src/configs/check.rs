@@ -0,0 +1,403 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    fs, io,
+    path::{Path, PathBuf},
+    process,
+};
+
+use tracing_subscriber::EnvFilter;
+
+use super::{
+    client::{Config, RawClient, DEFAULT_PROFILE_NAME},
+    overrides::{read_secret_file, Overrides, ENV_PREFIX},
+    paths::default_session_file_path,
+};
+
+const API_HASH_LEN: usize = 32;
+/// E.164 limits of the phone number without `+`
+const PHONE_NUMBER_MIN_LEN: usize = 5;
+const PHONE_NUMBER_MAX_LEN: usize = 15;
+
+/// Single problem of the config. The message continues the key, e.g. "`api_id` is unset".
+pub struct Problem {
+    pub profile: Option<Box<str>>,
+    pub key: &'static str,
+    pub message: String,
+}
+
+impl Display for Problem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.profile {
+            Some(ref profile) => write!(
+                f,
+                "Profile `{profile}`: `{key}` {message}",
+                key = self.key,
+                message = self.message,
+            ),
+            None => write!(
+                f,
+                "`{key}` {message}",
+                key = self.key,
+                message = self.message,
+            ),
+        }
+    }
+}
+
+pub fn check_api_id(api_id: i32) -> Result<(), String> {
+    if api_id > 0 {
+        Ok(())
+    } else {
+        Err(format!(
+            "must be a positive number, but it's {api_id}. Get it on https://my.telegram.org/apps"
+        ))
+    }
+}
+
+pub fn check_api_hash(api_hash: &str) -> Result<(), String> {
+    if api_hash.len() == API_HASH_LEN && api_hash.chars().all(|c| c.is_ascii_hexdigit()) {
+        Ok(())
+    } else {
+        Err(format!(
+            "must be {API_HASH_LEN} hexadecimal characters. Get it on https://my.telegram.org/apps"
+        ))
+    }
+}
+
+/// Empty phone number is valid, it means login by QR code
+pub fn check_phone_number(phone_number: &str) -> Result<(), String> {
+    let phone_number = phone_number.trim();
+    let digits = phone_number.strip_prefix('+').unwrap_or(phone_number);
+
+    if phone_number.is_empty() {
+        Ok(())
+    } else if !digits.chars().all(|c| c.is_ascii_digit()) {
+        Err(format!(
+            "`{phone_number}` must contain only digits with optional leading `+`, e.g. `+15551234567`"
+        ))
+    } else if !(PHONE_NUMBER_MIN_LEN..=PHONE_NUMBER_MAX_LEN).contains(&digits.len()) {
+        Err(format!(
+            "`{phone_number}` must have from {PHONE_NUMBER_MIN_LEN} to {PHONE_NUMBER_MAX_LEN} digits with the country code"
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// The session is written to a temporary file near the session file first and missing
+/// directories are created, so the nearest existing directory has to be writable too. It's
+/// checked the same way, by creating and removing a temporary file in it.
+pub fn check_session_file_path(path: &Path) -> Result<(), String> {
+    match fs::metadata(path) {
+        Ok(metadata) if metadata.is_dir() => {
+            return Err(format!("`{path}` is a directory", path = path.display()))
+        }
+        Ok(metadata) if metadata.permissions().readonly() => {
+            return Err(format!("`{path}` isn't writable", path = path.display()))
+        }
+        Ok(_) => {}
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+        Err(err) => {
+            return Err(format!(
+                "`{path}` can't be accessed: {err}",
+                path = path.display(),
+            ))
+        }
+    }
+
+    let dir = path
+        .ancestors()
+        .skip(1)
+        .map(|dir| {
+            if dir.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                dir
+            }
+        })
+        .find(|dir| dir.exists())
+        .unwrap_or(Path::new("."));
+
+    match fs::metadata(dir) {
+        Ok(metadata) if !metadata.is_dir() => Err(format!(
+            "`{path}` is in `{dir}` which isn't a directory",
+            path = path.display(),
+            dir = dir.display(),
+        )),
+        Ok(_) => {
+            let probe_path = dir.join(format!(
+                ".tg_old_chats_manager-check-{pid}.tmp",
+                pid = process::id(),
+            ));
+
+            fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&probe_path)
+                .and_then(|_| fs::remove_file(&probe_path))
+                .map_err(|err| {
+                    format!(
+                        "`{path}` is in directory `{dir}` which isn't writable: {err}",
+                        path = path.display(),
+                        dir = dir.display(),
+                    )
+                })
+        }
+        Err(err) => Err(format!(
+            "`{path}` is in directory `{dir}` which can't be accessed: {err}",
+            path = path.display(),
+            dir = dir.display(),
+        )),
+    }
+}
+
+pub fn check_logging_directives(directives: &str) -> Result<(), String> {
+    EnvFilter::try_new(directives)
+        .map(drop)
+        .map_err(|err| format!("`{directives}` is invalid: {err}"))
+}
+
+struct Problems(Vec<Problem>);
+
+impl Problems {
+    fn push(&mut self, profile: Option<&str>, key: &'static str, result: Result<(), String>) {
+        if let Err(message) = result {
+            self.0.push(Problem {
+                profile: profile.map(Into::into),
+                key,
+                message,
+            });
+        }
+    }
+
+    fn missing(&mut self, profile: &str, key: &'static str) {
+        self.push(
+            Some(profile),
+            key,
+            Err(format!(
+                "is unset. Specify it in the config or by `{ENV_PREFIX}{env}` environment variable",
+                env = key.to_uppercase(),
+            )),
+        );
+    }
+
+    fn secret(
+        &mut self,
+        profile: &str,
+        key: &'static str,
+        value: Option<&str>,
+        file: Option<&Path>,
+    ) -> Option<Box<str>> {
+        let Some(path) = file else {
+            return value.map(Into::into);
+        };
+
+        match read_secret_file(path) {
+            Ok(secret) => Some(secret),
+            Err(err) => {
+                self.push(
+                    Some(profile),
+                    key,
+                    Err(format!(
+                        "can't be read from `{path}`: {err}",
+                        path = path.display(),
+                    )),
+                );
+                None
+            }
+        }
+    }
+
+    fn client(
+        &mut self,
+        profile: &str,
+        client: &RawClient,
+        default_session_file_path: PathBuf,
+        overrides: &Overrides,
+    ) {
+        match overrides.api_id.or(client.api_id) {
+            Some(api_id) => self.push(Some(profile), "api_id", check_api_id(api_id)),
+            None => self.missing(profile, "api_id"),
+        }
+
+        let api_hash = self.secret(
+            profile,
+            "api_hash_file",
+            client.api_hash.as_deref(),
+            client.api_hash_file.as_deref(),
+        );
+        match overrides.api_hash.as_deref().or(api_hash.as_deref()) {
+            Some(api_hash) => self.push(Some(profile), "api_hash", check_api_hash(api_hash)),
+            // The unreadable file is already reported
+            None if client.api_hash_file.is_some() => {}
+            None => self.missing(profile, "api_hash"),
+        }
+
+        let phone_number = self.secret(
+            profile,
+            "phone_number_file",
+            client.phone_number.as_deref(),
+            client.phone_number_file.as_deref(),
+        );
+        if let Some(phone_number) = overrides
+            .phone_number
+            .as_deref()
+            .or(phone_number.as_deref())
+        {
+            self.push(
+                Some(profile),
+                "phone_number",
+                check_phone_number(phone_number),
+            );
+        }
+
+        self.secret(
+            profile,
+            "password_file",
+            None,
+            client.password_file.as_deref(),
+        );
+        self.secret(
+            profile,
+            "session_passphrase_file",
+            None,
+            client.session_passphrase_file.as_deref(),
+        );
+
+        let session_file_path = overrides
+            .session_file_path
+            .as_deref()
+            .or(client.session_file_path.as_deref())
+            .unwrap_or(&default_session_file_path);
+        self.push(
+            Some(profile),
+            "session_file_path",
+            check_session_file_path(session_file_path),
+        );
+    }
+}
+
+/// Check the config offline and collect all problems. Values from the overrides take precedence
+/// like in regular runs, so a value that's unset in the config isn't a problem if it's overridden.
+/// The error is returned if the config can't be parsed, e.g. it has an unknown key or a value of
+/// a wrong type, since nothing else can be checked then.
+pub fn check(raw: &str, overrides: &Overrides) -> Result<Vec<Problem>, toml::de::Error> {
+    let config = Config::parse_raw_toml(raw)?;
+    let mut problems = Problems(vec![]);
+
+    let profiles = config.raw_profiles();
+    if profiles.is_empty() {
+        problems.client(
+            DEFAULT_PROFILE_NAME,
+            &RawClient::default(),
+            default_session_file_path(DEFAULT_PROFILE_NAME),
+            overrides,
+        );
+    }
+    for (name, client, default_session_file_path) in profiles {
+        problems.client(name, client, default_session_file_path, overrides);
+    }
+
+    problems.push(
+        None,
+        "logging.directives",
+        check_logging_directives(&config.logging.directives),
+    );
+
+    Ok(problems.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+
+    const API_HASH: &str = "0123456789abcdef0123456789ABCDEF";
+
+    fn messages(raw: &str) -> Vec<String> {
+        check(raw, &Overrides::default())
+            .unwrap()
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn api_hash_is_32_hex_characters() {
+        assert!(check_api_hash(API_HASH).is_ok());
+        assert!(check_api_hash("").is_err());
+        assert!(check_api_hash(&API_HASH[1..]).is_err());
+        assert!(check_api_hash(&API_HASH.replace('0', "g")).is_err());
+    }
+
+    #[test]
+    fn phone_number_has_digits_with_optional_plus() {
+        for phone_number in ["", "+15551234567", "15551234567", " +79991234567 "] {
+            assert!(check_phone_number(phone_number).is_ok(), "{phone_number}");
+        }
+        for phone_number in [
+            "+1 555 123",
+            "phone",
+            "+1234",
+            "+1234567890123456",
+            "++1555123",
+        ] {
+            assert!(check_phone_number(phone_number).is_err(), "{phone_number}");
+        }
+    }
+
+    #[test]
+    fn session_directory_is_checked_by_a_temporary_file() {
+        let dir = env::temp_dir().join(format!(
+            "tg_old_chats_manager-session-dir-{pid}",
+            pid = process::id(),
+        ));
+        fs::create_dir_all(&dir).unwrap();
+
+        let result = check_session_file_path(&dir.join("missing/client.session"));
+        let left_files = fs::read_dir(&dir).unwrap().count();
+        fs::remove_dir(&dir).unwrap();
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(left_files, 0);
+    }
+
+    #[test]
+    fn logging_directives_are_parsed() {
+        assert!(check_logging_directives("info,grammers_session=warn").is_ok());
+        assert!(check_logging_directives("info,grammers_session=loud").is_err());
+    }
+
+    #[test]
+    fn problems_are_collected_from_the_parsed_config() {
+        let problems = messages(&format!(
+            "[client]\napi_id = 0\napi_hash = \"{API_HASH}\"\nsession_file_path = \"client.session\"\n\n[logging]\ndirectives = \"info,foo=loud\"\n",
+        ));
+
+        assert_eq!(problems.len(), 2, "{problems:?}");
+        assert!(problems[0].starts_with("Profile `default`: `api_id` must be a positive number"));
+        assert!(problems[1].starts_with("`logging.directives` `info,foo=loud` is invalid"));
+    }
+
+    #[test]
+    fn unknown_keys_and_wrong_types_are_reported() {
+        let misspelled = check(
+            "[client]\napi_id = 1\napi_hsh = \"\"\n",
+            &Overrides::default(),
+        );
+        assert!(misspelled.is_err_and(|err| err.to_string().contains("api_hsh")));
+
+        let wrong_type = check("[client]\napi_id = \"1\"\n", &Overrides::default());
+        assert!(wrong_type.is_err());
+
+        let unknown_section = check("[logs]\ndirectives = \"info\"\n", &Overrides::default());
+        assert!(unknown_section.is_err_and(|err| err.to_string().contains("logs")));
+
+        let duplicate_default = check(
+            "[client]\napi_id = 1\n\n[profiles.default]\napi_id = 2\n",
+            &Overrides::default(),
+        );
+        assert!(duplicate_default.is_err_and(|err| err.to_string().contains("profiles.default")));
+    }
+}
@@ -1,28 +1,56 @@
 use std::{
+    collections::BTreeMap,
+    error::Error,
+    fmt::{self, Display, Formatter},
     fs, io,
     path::{Path, PathBuf},
-    str::FromStr,
 };
 
-use serde::Deserialize;
+use serde::{de, Deserialize};
 
-fn default_session_file_path() -> PathBuf {
-    PathBuf::from_str("configs/client.session").expect("Incorrect default config file path")
-}
+use super::{
+    overrides::{read_secret_file, Overrides, ENV_PREFIX},
+    paths::{default_ignore_file_path, default_session_file_path},
+};
+
+pub const DEFAULT_PROFILE_NAME: &str = "default";
+
+/// Directives for new configs, which hide noisy logs of the client
+pub const RECOMMENDED_LOGGING_DIRECTIVES: &str =
+    "info,os_info=warn,grammers_mtsender=warn,grammers_session=warn,grammers_mtproto=warn";
 
 fn default_logging_directives() -> Box<str> {
     "info".to_owned().into_boxed_str()
 }
 
-#[derive(Deserialize)]
+/// Client section as it's written in the config. Each secret can be specified directly or read
+/// from the file by `*_file` key.
+#[derive(Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RawClient {
+    pub(super) api_id: Option<i32>,
+    pub(super) api_hash: Option<Box<str>>,
+    pub(super) api_hash_file: Option<PathBuf>,
+
+    pub(super) password: Option<Box<str>>,
+    pub(super) password_file: Option<PathBuf>,
+    pub(super) session_file_path: Option<PathBuf>,
+    pub(super) phone_number: Option<Box<str>>,
+    pub(super) phone_number_file: Option<PathBuf>,
+    #[serde(default)]
+    pub(super) encrypt_session: bool,
+    pub(super) session_passphrase_file: Option<PathBuf>,
+}
+
 pub struct Client {
     pub api_id: i32,
     pub api_hash: String,
 
     password: Option<Box<str>>,
-    #[serde(default = "default_session_file_path")]
     session_file_path: PathBuf,
-    phone_number: Box<str>,
+    phone_number: Option<Box<str>>,
+    encrypt_session: bool,
+    session_passphrase: Option<Box<str>>,
 }
 
 impl Client {
@@ -37,30 +65,323 @@ impl Client {
         &self.session_file_path
     }
 
-    pub fn phone_number(&self) -> &str {
-        if self.phone_number.starts_with('+') {
-            &self.phone_number[1..]
-        } else {
-            &self.phone_number
+    pub fn phone_number(&self) -> Option<&str> {
+        match self.phone_number.as_deref().map(str::trim) {
+            Some("") | None => None,
+            Some(phone_number) => Some(phone_number.strip_prefix('+').unwrap_or(phone_number)),
         }
     }
+
+    pub fn encrypt_session(&self) -> bool {
+        self.encrypt_session
+    }
+
+    pub fn session_passphrase(&self) -> Option<&str> {
+        self.session_passphrase.as_deref()
+    }
 }
 
 #[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct Logging {
     #[serde(default = "default_logging_directives")]
     pub directives: Box<str>,
 }
 
+impl Default for Logging {
+    fn default() -> Self {
+        Self {
+            directives: default_logging_directives(),
+        }
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Ignore {
+    file_path: Option<PathBuf>,
+}
+
+pub struct Profile<'a> {
+    pub name: &'a str,
+    pub client: Client,
+}
+
+#[derive(Debug)]
+pub enum ProfileError {
+    NotFound {
+        name: Box<str>,
+        available: Box<[Box<str>]>,
+    },
+    NotSpecified {
+        available: Box<[Box<str>]>,
+    },
+    MissingValue {
+        profile: Box<str>,
+        key: &'static str,
+    },
+    SecretFile {
+        profile: Box<str>,
+        key: &'static str,
+        path: PathBuf,
+        source: io::Error,
+    },
+}
+
+impl Display for ProfileError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ProfileError::NotFound { name, available } => write!(
+                f,
+                "Profile `{name}` not found in the config. Available profiles: {available}",
+                available = available.join(", "),
+            ),
+            ProfileError::NotSpecified { available } => write!(
+                f,
+                "The config has several profiles, specify one of them with `--profile`: {available}",
+                available = available.join(", "),
+            ),
+            ProfileError::MissingValue { profile, key } => write!(
+                f,
+                "`{key}` of profile `{profile}` is unset. Specify it in the config or by `{ENV_PREFIX}{env}` environment variable",
+                env = key.to_uppercase(),
+            ),
+            ProfileError::SecretFile {
+                profile,
+                key,
+                path,
+                source,
+            } => write!(
+                f,
+                "Can't read `{key}_file` of profile `{profile}` from `{path}`: {source}",
+                path = path.display(),
+            ),
+        }
+    }
+}
+
+impl Error for ProfileError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ProfileError::SecretFile { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl RawClient {
+    fn secret(
+        profile: &str,
+        key: &'static str,
+        value: Option<&str>,
+        file: Option<&Path>,
+    ) -> Result<Option<Box<str>>, ProfileError> {
+        match file {
+            Some(path) => {
+                read_secret_file(path)
+                    .map(Some)
+                    .map_err(|source| ProfileError::SecretFile {
+                        profile: profile.into(),
+                        key,
+                        path: path.to_path_buf(),
+                        source,
+                    })
+            }
+            None => Ok(value.map(Into::into)),
+        }
+    }
+
+    /// Apply overrides and secret files. The precedence is: overrides, `*_file` keys and
+    /// values from the config.
+    fn resolve(
+        &self,
+        profile: &str,
+        default_session_file_path: PathBuf,
+        overrides: Overrides,
+    ) -> Result<Client, ProfileError> {
+        let api_hash = match overrides.api_hash {
+            Some(api_hash) => api_hash,
+            None => Self::secret(
+                profile,
+                "api_hash",
+                self.api_hash.as_deref(),
+                self.api_hash_file.as_deref(),
+            )?
+            .ok_or_else(|| ProfileError::MissingValue {
+                profile: profile.into(),
+                key: "api_hash",
+            })?,
+        };
+        let password = match overrides.password {
+            Some(password) => Some(password),
+            None => Self::secret(
+                profile,
+                "password",
+                self.password.as_deref(),
+                self.password_file.as_deref(),
+            )?,
+        };
+        let phone_number = match overrides.phone_number {
+            Some(phone_number) => Some(phone_number),
+            None => Self::secret(
+                profile,
+                "phone_number",
+                self.phone_number.as_deref(),
+                self.phone_number_file.as_deref(),
+            )?,
+        };
+        let session_passphrase = match overrides.session_passphrase {
+            Some(session_passphrase) => Some(session_passphrase),
+            None => Self::secret(
+                profile,
+                "session_passphrase",
+                None,
+                self.session_passphrase_file.as_deref(),
+            )?,
+        };
+
+        Ok(Client {
+            api_id: overrides
+                .api_id
+                .or(self.api_id)
+                .ok_or_else(|| ProfileError::MissingValue {
+                    profile: profile.into(),
+                    key: "api_id",
+                })?,
+            api_hash: api_hash.into(),
+            password,
+            session_file_path: overrides
+                .session_file_path
+                .or_else(|| self.session_file_path.clone())
+                .unwrap_or(default_session_file_path),
+            phone_number,
+            encrypt_session: self.encrypt_session,
+            session_passphrase,
+        })
+    }
+}
+
+/// Unknown keys are rejected, since a misspelled optional key would be silently ignored
 #[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct Config {
-    pub client: Client,
+    pub client: Option<RawClient>,
+    #[serde(default)]
+    pub profiles: BTreeMap<Box<str>, RawClient>,
+    #[serde(default)]
     pub logging: Logging,
+    #[serde(default)]
+    pub ignore: Ignore,
 }
 
 impl Config {
+    /// `[client]` section is `default` profile already, so `[profiles.default]` is rejected with
+    /// it instead of one of them being silently shadowed
     pub fn parse_raw_toml(raw: impl AsRef<str>) -> Result<Self, toml::de::Error> {
-        toml::from_str(raw.as_ref())
+        let config: Self = toml::from_str(raw.as_ref())?;
+
+        if config.client.is_some() && config.profiles.contains_key(DEFAULT_PROFILE_NAME) {
+            return Err(de::Error::custom(format!(
+                "`[client]` section is `{DEFAULT_PROFILE_NAME}` profile, so it can't be used with `[profiles.{DEFAULT_PROFILE_NAME}]`"
+            )));
+        }
+
+        Ok(config)
+    }
+
+    pub fn ignore_file_path(&self) -> PathBuf {
+        self.ignore
+            .file_path
+            .clone()
+            .unwrap_or_else(default_ignore_file_path)
+    }
+
+    pub(super) fn raw_profiles(&self) -> Vec<(&str, &RawClient, PathBuf)> {
+        self.client
+            .iter()
+            .map(|client| {
+                (
+                    DEFAULT_PROFILE_NAME,
+                    client,
+                    default_session_file_path(DEFAULT_PROFILE_NAME),
+                )
+            })
+            .chain(
+                self.profiles
+                    .iter()
+                    .map(|(name, client)| (&**name, client, default_session_file_path(name))),
+            )
+            .collect()
+    }
+
+    /// All accounts of the config. `[client]` section is named as `default` profile. Only the
+    /// [shared](Overrides::shared) overrides are applied.
+    pub fn profiles(&self, overrides: &Overrides) -> Result<Vec<Profile<'_>>, ProfileError> {
+        self.raw_profiles()
+            .into_iter()
+            .map(|(name, client, session_file_path)| {
+                Ok(Profile {
+                    name,
+                    client: client.resolve(name, session_file_path, overrides.shared())?,
+                })
+            })
+            .collect()
+    }
+
+    /// Account by name or the only one if the name isn't specified. If the config has no
+    /// accounts, the `default` one is built from the overrides only.
+    pub fn profile(
+        &self,
+        name: Option<&str>,
+        overrides: Overrides,
+    ) -> Result<Profile<'_>, ProfileError> {
+        let mut profiles = self.raw_profiles();
+        let available = || {
+            self.raw_profiles()
+                .into_iter()
+                .map(|(name, ..)| name.into())
+                .collect()
+        };
+
+        let (name, client, session_file_path) = match name {
+            Some(name) => profiles
+                .into_iter()
+                .find(|(profile_name, ..)| *profile_name == name)
+                .ok_or_else(|| ProfileError::NotFound {
+                    name: name.into(),
+                    available: available(),
+                })?,
+            None => match profiles.len() {
+                0 => {
+                    return Ok(Profile {
+                        name: DEFAULT_PROFILE_NAME,
+                        client: RawClient::default().resolve(
+                            DEFAULT_PROFILE_NAME,
+                            default_session_file_path(DEFAULT_PROFILE_NAME),
+                            overrides,
+                        )?,
+                    })
+                }
+                1 => profiles.remove(0),
+                _ => match self.client {
+                    Some(ref client) => (
+                        DEFAULT_PROFILE_NAME,
+                        client,
+                        default_session_file_path(DEFAULT_PROFILE_NAME),
+                    ),
+                    None => {
+                        return Err(ProfileError::NotSpecified {
+                            available: available(),
+                        })
+                    }
+                },
+            },
+        };
+
+        Ok(Profile {
+            name,
+            client: client.resolve(name, session_file_path, overrides)?,
+        })
     }
 }
 
@@ -0,0 +1,262 @@
+use std::{
+    collections::BTreeSet,
+    error::Error,
+    fmt::{self, Display, Formatter},
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug)]
+pub enum IgnoreError {
+    Read {
+        path: PathBuf,
+        source: io::Error,
+    },
+    Parse {
+        path: PathBuf,
+        source: toml::de::Error,
+    },
+    Write {
+        path: PathBuf,
+        source: io::Error,
+    },
+    Serialize(toml::ser::Error),
+    InvalidPattern {
+        pattern: Box<str>,
+        source: regex::Error,
+    },
+}
+
+impl Display for IgnoreError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            IgnoreError::Read { path, source } => write!(
+                f,
+                "Can't read ignore list `{path}`: {source}",
+                path = path.display(),
+            ),
+            IgnoreError::Parse { path, source } => write!(
+                f,
+                "Ignore list `{path}` is invalid: {source}",
+                path = path.display(),
+            ),
+            IgnoreError::Write { path, source } => write!(
+                f,
+                "Can't write ignore list `{path}`: {source}",
+                path = path.display(),
+            ),
+            IgnoreError::Serialize(err) => write!(f, "Can't serialize ignore list: {err}"),
+            IgnoreError::InvalidPattern { pattern, source } => {
+                write!(f, "Title pattern `{pattern}` is invalid: {source}")
+            }
+        }
+    }
+}
+
+impl Error for IgnoreError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            IgnoreError::Read { source, .. } | IgnoreError::Write { source, .. } => Some(source),
+            IgnoreError::Parse { source, .. } => Some(source),
+            IgnoreError::Serialize(err) => Some(err),
+            IgnoreError::InvalidPattern { source, .. } => Some(source),
+        }
+    }
+}
+
+/// Usernames are case-insensitive and can be written with `@`
+fn normalize_username(username: &str) -> Box<str> {
+    username
+        .trim()
+        .trim_start_matches('@')
+        .to_lowercase()
+        .into_boxed_str()
+}
+
+/// Chats that are never flagged by the analysis. It's stored in a separate file, because it's
+/// edited by `ignore` commands.
+#[derive(Default, Deserialize, Serialize)]
+pub struct IgnoreList {
+    #[serde(default)]
+    pub ids: BTreeSet<i64>,
+    #[serde(default)]
+    pub usernames: BTreeSet<Box<str>>,
+    /// Case-insensitive regular expressions of chat titles
+    #[serde(default)]
+    pub title_patterns: BTreeSet<Box<str>>,
+}
+
+impl IgnoreList {
+    /// Missing file is an empty list
+    pub fn load(path: &Path) -> Result<Self, IgnoreError> {
+        let raw = match fs::read_to_string(path) {
+            Ok(raw) => raw,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(source) => {
+                return Err(IgnoreError::Read {
+                    path: path.to_path_buf(),
+                    source,
+                })
+            }
+        };
+
+        toml::from_str(&raw).map_err(|source| IgnoreError::Parse {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), IgnoreError> {
+        let raw = toml::to_string(self).map_err(IgnoreError::Serialize)?;
+
+        match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => fs::create_dir_all(dir),
+            _ => Ok(()),
+        }
+        .and_then(|()| fs::write(path, raw))
+        .map_err(|source| IgnoreError::Write {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+
+    /// Returns `false` if the username is already in the list
+    pub fn add_username(&mut self, username: &str) -> bool {
+        self.usernames.insert(normalize_username(username))
+    }
+
+    /// Returns `false` if the username isn't in the list
+    pub fn remove_username(&mut self, username: &str) -> bool {
+        self.usernames.remove(&normalize_username(username))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty() && self.usernames.is_empty() && self.title_patterns.is_empty()
+    }
+
+    pub fn matcher(&self) -> Result<IgnoreMatcher, IgnoreError> {
+        let titles = self
+            .title_patterns
+            .iter()
+            .map(|pattern| {
+                RegexBuilder::new(pattern)
+                    .case_insensitive(true)
+                    .build()
+                    .map_err(|source| IgnoreError::InvalidPattern {
+                        pattern: pattern.clone(),
+                        source,
+                    })
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(IgnoreMatcher {
+            ids: self.ids.clone(),
+            usernames: self
+                .usernames
+                .iter()
+                .map(|username| normalize_username(username))
+                .collect(),
+            titles,
+        })
+    }
+}
+
+/// Ignore list with compiled title patterns
+#[derive(Default)]
+pub struct IgnoreMatcher {
+    ids: BTreeSet<i64>,
+    usernames: BTreeSet<Box<str>>,
+    titles: Vec<Regex>,
+}
+
+impl IgnoreMatcher {
+    pub fn is_ignored(&self, id: i64, username: Option<&str>, title: Option<&str>) -> bool {
+        self.ids.contains(&id)
+            || username
+                .is_some_and(|username| self.usernames.contains(&normalize_username(username)))
+            || title.is_some_and(|title| self.titles.iter().any(|regex| regex.is_match(title)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matcher(ids: &[i64], usernames: &[&str], title_patterns: &[&str]) -> IgnoreMatcher {
+        let mut list = IgnoreList {
+            ids: ids.iter().copied().collect(),
+            title_patterns: title_patterns
+                .iter()
+                .map(|&pattern| pattern.into())
+                .collect(),
+            ..IgnoreList::default()
+        };
+        for username in usernames {
+            list.add_username(username);
+        }
+
+        list.matcher().unwrap()
+    }
+
+    #[test]
+    fn chats_are_matched_by_id() {
+        let matcher = matcher(&[1, -100], &[], &[]);
+
+        assert!(matcher.is_ignored(1, None, None));
+        assert!(matcher.is_ignored(-100, Some("any"), Some("Any")));
+        assert!(!matcher.is_ignored(2, None, None));
+    }
+
+    #[test]
+    fn usernames_are_matched_ignoring_case_and_at() {
+        let matcher = matcher(&[], &["@Family_Chat", "news"], &[]);
+
+        for username in [
+            "family_chat",
+            "@FAMILY_CHAT",
+            " Family_Chat ",
+            "@news",
+            "NEWS",
+        ] {
+            assert!(matcher.is_ignored(1, Some(username), None), "{username}");
+        }
+        assert!(!matcher.is_ignored(1, Some("family"), None));
+        assert!(!matcher.is_ignored(1, None, Some("family_chat")));
+    }
+
+    #[test]
+    fn titles_are_matched_by_case_insensitive_patterns() {
+        let matcher = matcher(&[], &[], &["^announcements", "family"]);
+
+        assert!(matcher.is_ignored(1, None, Some("Announcements of the project")));
+        assert!(matcher.is_ignored(1, None, Some("Our FAMILY")));
+        assert!(!matcher.is_ignored(1, None, Some("Project announcements")));
+        assert!(!matcher.is_ignored(1, Some("family"), None));
+    }
+
+    #[test]
+    fn invalid_pattern_is_rejected() {
+        let list = IgnoreList {
+            title_patterns: ["(unclosed".into()].into(),
+            ..IgnoreList::default()
+        };
+
+        assert!(matches!(
+            list.matcher(),
+            Err(IgnoreError::InvalidPattern { .. }),
+        ));
+    }
+
+    #[test]
+    fn usernames_are_removed_in_any_form() {
+        let mut list = IgnoreList::default();
+
+        assert!(list.add_username("@News"));
+        assert!(!list.add_username("news"));
+        assert!(list.remove_username("NEWS"));
+        assert!(list.is_empty());
+    }
+}
@@ -0,0 +1,254 @@
+use std::{
+    env,
+    error::Error,
+    fmt::{self, Display, Formatter},
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+pub const ENV_PREFIX: &str = "TGOCM_";
+
+pub fn read_secret_file(path: impl AsRef<Path>) -> Result<Box<str>, io::Error> {
+    fs::read_to_string(path).map(|secret| secret.trim_end_matches(['\r', '\n']).into())
+}
+
+#[derive(Debug)]
+pub enum OverridesError {
+    InvalidEnv { var: Box<str>, value: Box<str> },
+    SecretFile { path: PathBuf, source: io::Error },
+}
+
+impl Display for OverridesError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            OverridesError::InvalidEnv { var, value } => {
+                write!(
+                    f,
+                    "Environment variable `{var}` has invalid value `{value}`"
+                )
+            }
+            OverridesError::SecretFile { path, source } => write!(
+                f,
+                "Can't read secret file `{path}`: {source}",
+                path = path.display(),
+            ),
+        }
+    }
+}
+
+impl Error for OverridesError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            OverridesError::InvalidEnv { .. } => None,
+            OverridesError::SecretFile { source, .. } => Some(source),
+        }
+    }
+}
+
+/// Client settings that take precedence over the config file
+#[derive(Default)]
+pub struct Overrides {
+    pub api_id: Option<i32>,
+    pub api_hash: Option<Box<str>>,
+    pub password: Option<Box<str>>,
+    pub phone_number: Option<Box<str>>,
+    pub session_file_path: Option<PathBuf>,
+    pub session_passphrase: Option<Box<str>>,
+}
+
+/// Value of the environment variable by its full name
+type Lookup<'a> = &'a dyn Fn(&str) -> Option<String>;
+
+fn var(lookup: Lookup, name: &str) -> Option<(Box<str>, Box<str>)> {
+    let var = format!("{ENV_PREFIX}{name}");
+
+    match lookup(&var) {
+        Some(value) if !value.is_empty() => Some((var.into(), value.into())),
+        _ => None,
+    }
+}
+
+/// `TGOCM_<NAME>` value or content of the file from `TGOCM_<NAME>_FILE`
+fn secret_var(lookup: Lookup, name: &str) -> Result<Option<Box<str>>, OverridesError> {
+    if let Some((_, value)) = var(lookup, name) {
+        return Ok(Some(value));
+    }
+
+    match var(lookup, &format!("{name}_FILE")) {
+        Some((_, path)) => {
+            read_secret_file(&*path)
+                .map(Some)
+                .map_err(|source| OverridesError::SecretFile {
+                    path: PathBuf::from(&*path),
+                    source,
+                })
+        }
+        None => Ok(None),
+    }
+}
+
+impl Overrides {
+    pub fn from_env() -> Result<Self, OverridesError> {
+        Self::from_vars(&|name| env::var(name).ok())
+    }
+
+    fn from_vars(lookup: Lookup) -> Result<Self, OverridesError> {
+        let api_id = match var(lookup, "API_ID") {
+            Some((var, value)) => Some(
+                value
+                    .parse()
+                    .map_err(|_| OverridesError::InvalidEnv { var, value })?,
+            ),
+            None => None,
+        };
+
+        Ok(Self {
+            api_id,
+            api_hash: secret_var(lookup, "API_HASH")?,
+            password: secret_var(lookup, "PASSWORD")?,
+            phone_number: secret_var(lookup, "PHONE_NUMBER")?,
+            session_file_path: var(lookup, "SESSION_FILE_PATH")
+                .map(|(_, value)| PathBuf::from(&*value)),
+            session_passphrase: secret_var(lookup, "SESSION_PASSPHRASE")?,
+        })
+    }
+
+    /// Overrides for every profile at once: the app and the passphrase of the session files.
+    /// The account ones, like the phone number, are left to the config of each profile.
+    #[must_use]
+    pub fn shared(&self) -> Self {
+        Self {
+            api_id: self.api_id,
+            api_hash: self.api_hash.clone(),
+            session_passphrase: self.session_passphrase.clone(),
+            ..Self::default()
+        }
+    }
+
+    /// Merge overrides, values of `self` have higher priority
+    #[must_use]
+    pub fn or(self, other: Self) -> Self {
+        Self {
+            api_id: self.api_id.or(other.api_id),
+            api_hash: self.api_hash.or(other.api_hash),
+            password: self.password.or(other.password),
+            phone_number: self.phone_number.or(other.phone_number),
+            session_file_path: self.session_file_path.or(other.session_file_path),
+            session_passphrase: self.session_passphrase.or(other.session_passphrase),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, process};
+
+    use super::*;
+    use crate::configs::Config;
+
+    /// Secret file with a trailing newline, like most editors write it
+    fn secret_file(name: &str, secret: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!(
+            "tg_old_chats_manager_{name}_{pid}",
+            pid = process::id(),
+        ));
+        fs::write(&path, format!("{secret}\n")).unwrap();
+
+        path
+    }
+
+    fn from_vars(vars: &[(&str, &str)]) -> Result<Overrides, OverridesError> {
+        let vars = vars
+            .iter()
+            .map(|(name, value)| (format!("{ENV_PREFIX}{name}"), value.to_string()))
+            .collect::<HashMap<_, _>>();
+
+        Overrides::from_vars(&|name| vars.get(name).cloned())
+    }
+
+    #[test]
+    fn secrets_are_read_from_files() {
+        let path = secret_file("env_password", "file password");
+        let path = path.to_str().unwrap();
+
+        let overrides = from_vars(&[
+            ("API_ID", "42"),
+            ("API_HASH", "env hash"),
+            ("API_HASH_FILE", "/nonexistent"),
+            ("PASSWORD_FILE", path),
+            ("PHONE_NUMBER", ""),
+        ])
+        .unwrap();
+
+        assert_eq!(overrides.api_id, Some(42));
+        // The value has precedence over the file, so the missing file isn't read
+        assert_eq!(overrides.api_hash.as_deref(), Some("env hash"));
+        assert_eq!(overrides.password.as_deref(), Some("file password"));
+        // Empty variables are unset
+        assert_eq!(overrides.phone_number, None);
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn invalid_variables_are_rejected() {
+        assert!(matches!(
+            from_vars(&[("API_ID", "id")]),
+            Err(OverridesError::InvalidEnv { .. }),
+        ));
+        assert!(matches!(
+            from_vars(&[("PASSWORD_FILE", "/nonexistent")]),
+            Err(OverridesError::SecretFile { .. }),
+        ));
+    }
+
+    #[test]
+    fn cli_overrides_env_overrides_config() {
+        let phone_number_path = secret_file("config_phone_number", "+15550000000");
+        let config = Config::parse_raw_toml(format!(
+            "[client]\napi_id = 1\napi_hash = \"config hash\"\npassword = \"config password\"\nphone_number = \"+15551111111\"\nphone_number_file = {path:?}\n",
+            path = phone_number_path.to_str().unwrap(),
+        ))
+        .unwrap();
+        let cli = Overrides {
+            api_hash: Some("cli hash".into()),
+            ..Overrides::default()
+        };
+        let env = from_vars(&[("API_ID", "2"), ("API_HASH", "env hash")]).unwrap();
+
+        let client = config.profile(None, cli.or(env)).unwrap().client;
+
+        assert_eq!(client.api_id, 2);
+        assert_eq!(client.api_hash, "cli hash");
+        assert_eq!(client.password(), Some("config password"));
+        // The file has precedence over the value in the config
+        assert_eq!(client.phone_number(), Some("15550000000"));
+
+        fs::remove_file(phone_number_path).unwrap();
+    }
+
+    #[test]
+    fn only_shared_overrides_are_applied_to_all_profiles() {
+        let config = Config::parse_raw_toml(
+            "[profiles.work]\napi_id = 1\napi_hash = \"work hash\"\npassword = \"work password\"\n\n[profiles.personal]\napi_id = 1\n",
+        )
+        .unwrap();
+        let env = from_vars(&[
+            ("API_HASH", "env hash"),
+            ("PASSWORD", "env password"),
+            ("SESSION_PASSPHRASE", "env passphrase"),
+        ])
+        .unwrap();
+
+        let profiles = config.profiles(&env).unwrap();
+
+        assert_eq!(profiles.len(), 2);
+        for profile in &profiles {
+            assert_eq!(profile.client.api_hash, "env hash");
+            assert_eq!(profile.client.session_passphrase(), Some("env passphrase"));
+        }
+        // The password is of the account, so the config of each profile is used
+        assert_eq!(profiles[1].name, "work");
+        assert_eq!(profiles[1].client.password(), Some("work password"));
+    }
+}
@@ -0,0 +1,95 @@
+use std::{
+    env,
+    path::{Path, PathBuf},
+};
+
+use super::{client::DEFAULT_PROFILE_NAME, overrides::ENV_PREFIX};
+
+const APP_DIR_NAME: &str = "tg_old_chats_manager";
+const CONFIG_FILE_NAME: &str = "config.toml";
+const IGNORE_FILE_NAME: &str = "ignore.toml";
+/// Location of the config and sessions before XDG directories were supported
+const LEGACY_DIR: &str = "configs";
+
+/// XDG base directory from the variable or the fallback in the home directory. Relative paths are
+/// ignored as the specification requires.
+fn xdg_dir(var: &str, home_fallback: &str) -> Option<PathBuf> {
+    match env::var_os(var).map(PathBuf::from) {
+        Some(dir) if dir.is_absolute() => Some(dir),
+        _ => env::var_os("HOME")
+            .map(PathBuf::from)
+            .filter(|home| home.is_absolute())
+            .map(|home| home.join(home_fallback)),
+    }
+}
+
+fn config_dir() -> Option<PathBuf> {
+    xdg_dir("XDG_CONFIG_HOME", ".config").map(|dir| dir.join(APP_DIR_NAME))
+}
+
+fn data_dir() -> Option<PathBuf> {
+    xdg_dir("XDG_DATA_HOME", ".local/share").map(|dir| dir.join(APP_DIR_NAME))
+}
+
+fn env_config_file_path() -> Option<PathBuf> {
+    env::var_os(format!("{ENV_PREFIX}CONFIG"))
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from)
+}
+
+fn legacy_config_file_path() -> PathBuf {
+    Path::new(LEGACY_DIR).join(CONFIG_FILE_NAME)
+}
+
+/// Config file path by the lookup chain: the explicit path, `$TGOCM_CONFIG`,
+/// `$XDG_CONFIG_HOME/tg_old_chats_manager/config.toml` and `configs/config.toml` in the current
+/// directory. The explicit path and the variable are returned as is, other ones only if they
+/// exist. Otherwise, all searched paths are returned as the error.
+pub fn find_config_file(explicit: Option<PathBuf>) -> Result<PathBuf, Box<[PathBuf]>> {
+    if let Some(path) = explicit.or_else(env_config_file_path) {
+        return Ok(path);
+    }
+
+    let candidates: Vec<PathBuf> = config_dir()
+        .map(|dir| dir.join(CONFIG_FILE_NAME))
+        .into_iter()
+        .chain([legacy_config_file_path()])
+        .collect();
+
+    match candidates.iter().find(|path| path.exists()) {
+        Some(path) => Ok(path.clone()),
+        None => Err(candidates.into()),
+    }
+}
+
+/// Path to create a new config at: the explicit path, `$TGOCM_CONFIG` or the XDG one
+pub fn new_config_file_path(explicit: Option<PathBuf>) -> PathBuf {
+    explicit
+        .or_else(env_config_file_path)
+        .or_else(|| config_dir().map(|dir| dir.join(CONFIG_FILE_NAME)))
+        .unwrap_or_else(legacy_config_file_path)
+}
+
+/// Ignore list is near the config, because it's edited by hand too
+pub fn default_ignore_file_path() -> PathBuf {
+    config_dir()
+        .unwrap_or_else(|| PathBuf::from(LEGACY_DIR))
+        .join(IGNORE_FILE_NAME)
+}
+
+/// Default session file of the profile in `$XDG_DATA_HOME/tg_old_chats_manager`. The session of
+/// `default` profile is `client.session`, others are `<name>.session`. A session from the legacy
+/// `configs` directory is still used if it exists, so existing users don't have to log in again.
+pub fn default_session_file_path(profile: &str) -> PathBuf {
+    let file_name = if profile == DEFAULT_PROFILE_NAME {
+        "client.session".to_owned()
+    } else {
+        format!("{profile}.session")
+    };
+
+    let legacy_path = Path::new(LEGACY_DIR).join(&file_name);
+    match data_dir().map(|dir| dir.join(&file_name)) {
+        Some(path) if path.exists() || !legacy_path.exists() => path,
+        _ => legacy_path,
+    }
+}
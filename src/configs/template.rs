@@ -0,0 +1,100 @@
+use std::path::PathBuf;
+
+use toml::Value;
+
+use super::client::RECOMMENDED_LOGGING_DIRECTIVES;
+
+/// Values of a new config. It's rendered as `[client]` and `[logging]` sections of
+/// [`super::Config`].
+pub struct Template {
+    pub api_id: i32,
+    pub api_hash: Box<str>,
+    pub phone_number: Option<Box<str>>,
+    pub password: Option<Box<str>>,
+    pub session_file_path: PathBuf,
+}
+
+/// Quoted and escaped TOML string
+fn string(value: &str) -> Value {
+    Value::String(value.to_owned())
+}
+
+impl Template {
+    pub fn render(&self) -> String {
+        let mut lines = vec![
+            "# Generated by `config init`. See `configs/config.toml.example` for all options."
+                .to_owned(),
+            "[client]".to_owned(),
+            format!("api_id = {api_id}", api_id = self.api_id),
+            format!("api_hash = {api_hash}", api_hash = string(&self.api_hash)),
+            format!(
+                "session_file_path = {path}",
+                path = string(&self.session_file_path.to_string_lossy()),
+            ),
+        ];
+
+        match self.phone_number {
+            Some(ref phone_number) => {
+                lines.push(format!("phone_number = {}", string(phone_number)));
+            }
+            None => {
+                lines.push(
+                    "# Phone number is unset, so you log in by scanning a QR code".to_owned(),
+                );
+            }
+        }
+        if let Some(ref password) = self.password {
+            lines.push(format!("password = {}", string(password)));
+        }
+
+        lines.push(String::new());
+        lines.push("[logging]".to_owned());
+        lines.push(format!(
+            "directives = {}",
+            string(RECOMMENDED_LOGGING_DIRECTIVES)
+        ));
+        lines.push(String::new());
+
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::configs::{Config, Overrides, DEFAULT_PROFILE_NAME};
+
+    fn template(phone_number: Option<&str>) -> Template {
+        Template {
+            api_id: 1234567,
+            api_hash: "0123456789abcdef0123456789abcdef".into(),
+            phone_number: phone_number.map(Into::into),
+            password: Some(r#"pass "word" \ with escapes"#.into()),
+            session_file_path: PathBuf::from(r"C:\Users\me\client.session"),
+        }
+    }
+
+    #[test]
+    fn rendered_config_is_parsed_back() {
+        for phone_number in [Some("+15551234567"), None] {
+            let template = template(phone_number);
+
+            let config = Config::parse_raw_toml(template.render()).unwrap();
+            let profile = config.profile(None, Overrides::default()).unwrap();
+
+            assert_eq!(profile.name, DEFAULT_PROFILE_NAME);
+            assert_eq!(profile.client.api_id, template.api_id);
+            assert_eq!(profile.client.api_hash, &*template.api_hash);
+            assert_eq!(profile.client.password(), template.password.as_deref());
+            assert_eq!(
+                profile.client.phone_number(),
+                phone_number.map(|phone_number| &phone_number[1..]),
+            );
+            assert_eq!(
+                profile.client.session_file_path(),
+                template.session_file_path,
+            );
+            assert_eq!(&*config.logging.directives, RECOMMENDED_LOGGING_DIRECTIVES,);
+        }
+    }
+}
@@ -0,0 +1,277 @@
+use std::{
+    error::Error as StdError,
+    fmt::{self, Display, Formatter},
+    io,
+    path::PathBuf,
+};
+
+use grammers_client::client::bots::InvocationError;
+use grammers_mtsender::RpcError;
+
+use crate::{
+    client::{
+        analyze::{MembersHistoryError, ReportError},
+        api::{ExportError, FixtureError},
+        auth::{AuthorizeError, InitError},
+        session_file::SessionError,
+    },
+    configs::{IgnoreError, OverridesError, ProfileError},
+};
+
+#[derive(Debug)]
+pub enum Error {
+    ConfigNotFound {
+        searched: Box<[PathBuf]>,
+    },
+    ConfigRead {
+        path: PathBuf,
+        source: io::Error,
+    },
+    ConfigParse {
+        path: PathBuf,
+        source: toml::de::Error,
+    },
+    ConfigCheck {
+        path: PathBuf,
+        problems: usize,
+    },
+    ConfigWrite {
+        path: PathBuf,
+        source: io::Error,
+    },
+    FolderNotFound {
+        name: Box<str>,
+        available: Box<[Box<str>]>,
+    },
+    FolderReplace {
+        name: Box<str>,
+    },
+    ChatNotSpecified,
+    Input(io::Error),
+    Terminal(io::Error),
+    Ignore(IgnoreError),
+    Report(ReportError),
+    MembersHistory(MembersHistoryError),
+    Fixture(FixtureError),
+    Export(ExportError),
+    Overrides(OverridesError),
+    Profile(ProfileError),
+    Session(SessionError),
+    Init(InitError),
+    Authorize(AuthorizeError),
+    Takeout(InvocationError),
+    ProfilesFailed {
+        names: Box<[Box<str>]>,
+    },
+    Command(InvocationError),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::ConfigNotFound { searched } => {
+                write!(
+                    f,
+                    "Config file not found in {searched}. Create it by `config init` command or specify its path with `--config-path` or `TGOCM_CONFIG` environment variable",
+                    searched = searched
+                        .iter()
+                        .map(|path| format!("`{}`", path.display()))
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                )
+            }
+            Error::ConfigRead { path, source } if source.kind() == io::ErrorKind::NotFound => {
+                write!(
+                    f,
+                    "Config file `{path}` not found. Create it by `config init` command, or copy `configs/config.toml.example` to it and fill it with your data, or specify another path with `--config-path`",
+                    path = path.display(),
+                )
+            }
+            Error::ConfigRead { path, source } => {
+                write!(
+                    f,
+                    "Can't read config file `{path}`: {source}",
+                    path = path.display(),
+                )
+            }
+            Error::ConfigParse { path, source } => {
+                write!(
+                    f,
+                    "Config file `{path}` is invalid: {source}",
+                    path = path.display(),
+                )
+            }
+            Error::ConfigCheck { path, problems } => {
+                write!(
+                    f,
+                    "Config file `{path}` has {problems} problem(s)",
+                    path = path.display(),
+                )
+            }
+            Error::ConfigWrite { path, source } => {
+                write!(
+                    f,
+                    "Can't write config file `{path}`: {source}",
+                    path = path.display(),
+                )
+            }
+            Error::FolderNotFound { name, available } if available.is_empty() => {
+                write!(f, "Folder `{name}` not found, the account has no folders")
+            }
+            Error::FolderNotFound { name, available } => {
+                write!(
+                    f,
+                    "Folder `{name}` not found. Available folders: {available}",
+                    available = available
+                        .iter()
+                        .map(|title| format!("`{title}`"))
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                )
+            }
+            Error::FolderReplace { name } => {
+                write!(
+                    f,
+                    "Folder `{name}` has rules or chats that aren't found, they would be lost. Use `--force` to replace them anyway",
+                )
+            }
+            Error::ChatNotSpecified => {
+                write!(f, "Specify the chat by `--id` or the chats by `--report`")
+            }
+            Error::Input(err) => write!(f, "Can't read the input: {err}"),
+            Error::Terminal(err) => write!(f, "Terminal UI error: {err}"),
+            Error::Ignore(err) => write!(f, "{err}"),
+            Error::Report(err) => write!(f, "{err}"),
+            Error::MembersHistory(err) => write!(f, "{err}"),
+            Error::Fixture(err) => write!(f, "{err}"),
+            Error::Export(err) => write!(f, "{err}"),
+            Error::Overrides(err) => write!(f, "{err}"),
+            Error::Profile(err) => write!(f, "{err}"),
+            Error::Session(err) => write!(f, "{err}"),
+            Error::Init(err) => write!(f, "{err}"),
+            Error::Authorize(err) => write!(f, "{err}"),
+            Error::Takeout(InvocationError::Rpc(RpcError {
+                code: 420,
+                value: Some(seconds),
+                ..
+            })) => {
+                write!(
+                    f,
+                    "For security reasons, you will be able to begin downloading your data in {seconds} seconds. All your devices are notified about the export request to make sure it's authorized",
+                )
+            }
+            Error::Takeout(err) => {
+                write!(
+                    f,
+                    "Can't start the takeout session to get left chats: {err}"
+                )
+            }
+            Error::ProfilesFailed { names } => {
+                write!(
+                    f,
+                    "Analysis failed for profile(s) {names}",
+                    names = names
+                        .iter()
+                        .map(|name| format!("`{name}`"))
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                )
+            }
+            Error::Command(err) => write!(f, "Telegram request failed: {err}"),
+        }
+    }
+}
+
+impl StdError for Error {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            Error::ConfigNotFound { .. } => None,
+            Error::ConfigRead { source, .. } => Some(source),
+            Error::ConfigParse { source, .. } => Some(source),
+            Error::ConfigCheck { .. } => None,
+            Error::ConfigWrite { source, .. } => Some(source),
+            Error::FolderNotFound { .. }
+            | Error::FolderReplace { .. }
+            | Error::ChatNotSpecified => None,
+            Error::Input(err) | Error::Terminal(err) => Some(err),
+            Error::Ignore(err) => Some(err),
+            Error::Report(err) => Some(err),
+            Error::MembersHistory(err) => Some(err),
+            Error::Fixture(err) => Some(err),
+            Error::Export(err) => Some(err),
+            Error::Overrides(err) => Some(err),
+            Error::Profile(err) => Some(err),
+            Error::Session(err) => Some(err),
+            Error::Init(err) => Some(err),
+            Error::Authorize(err) => Some(err),
+            Error::ProfilesFailed { .. } => None,
+            Error::Takeout(err) | Error::Command(err) => Some(err),
+        }
+    }
+}
+
+impl From<IgnoreError> for Error {
+    fn from(val: IgnoreError) -> Self {
+        Error::Ignore(val)
+    }
+}
+
+impl From<ReportError> for Error {
+    fn from(val: ReportError) -> Self {
+        Error::Report(val)
+    }
+}
+
+impl From<FixtureError> for Error {
+    fn from(val: FixtureError) -> Self {
+        Error::Fixture(val)
+    }
+}
+
+impl From<MembersHistoryError> for Error {
+    fn from(val: MembersHistoryError) -> Self {
+        Error::MembersHistory(val)
+    }
+}
+
+impl From<ExportError> for Error {
+    fn from(val: ExportError) -> Self {
+        Error::Export(val)
+    }
+}
+
+impl From<OverridesError> for Error {
+    fn from(val: OverridesError) -> Self {
+        Error::Overrides(val)
+    }
+}
+
+impl From<ProfileError> for Error {
+    fn from(val: ProfileError) -> Self {
+        Error::Profile(val)
+    }
+}
+
+impl From<SessionError> for Error {
+    fn from(val: SessionError) -> Self {
+        Error::Session(val)
+    }
+}
+
+impl From<InitError> for Error {
+    fn from(val: InitError) -> Self {
+        Error::Init(val)
+    }
+}
+
+impl From<AuthorizeError> for Error {
+    fn from(val: AuthorizeError) -> Self {
+        Error::Authorize(val)
+    }
+}
+
+impl From<InvocationError> for Error {
+    fn from(val: InvocationError) -> Self {
+        Error::Command(val)
+    }
+}
@@ -1,35 +1,197 @@
 mod cli;
 mod client;
 mod configs;
+mod errors;
 
-use cli::{models::Cli, parse as cli_parse, run as cli_run};
-use client::auth;
-use configs::{read_raw_toml, Config};
+use std::{path::PathBuf, process::ExitCode};
+
+use cli::{
+    commands,
+    models::{Analyze, Cli, ClientOverrides, Command, Commands, OfflineCommand},
+    overrides as cli_overrides, parse as cli_parse, run as cli_run,
+};
+use client::{auth, session_file::SessionFile};
+use configs::{
+    find_config_file, new_config_file_path, read_raw_toml, ClientConfig, Config, Overrides,
+};
+use errors::Error;
+use grammers_client::Client;
 use tracing::info;
 use tracing_subscriber::{fmt, layer::SubscriberExt as _, util::SubscriberInitExt as _, EnvFilter};
 
-#[tokio::main]
-async fn main() {
-    let Cli {
-        config_file_path,
-        command,
-    } = cli_parse();
+async fn connect(config: &ClientConfig, authorize: bool) -> Result<Client, Error> {
+    let session_file = SessionFile::open(config)?;
 
-    let raw = read_raw_toml(config_file_path).expect("Error while reading config file");
-    let config = Config::parse_raw_toml(raw).expect("Error while parsing raw config");
+    info!("Init client");
+    let client = auth::init(config, &session_file).await?;
+    if !authorize {
+        return Ok(client);
+    }
+
+    info!("Authorize client");
+    let client = auth::authorize(client, config, &session_file).await?;
+
+    info!("Client connected and authorized successfully");
+
+    Ok(client)
+}
+
+fn read_config_file(config_file_path: Option<PathBuf>) -> Result<(PathBuf, Box<str>), Error> {
+    let config_file_path = find_config_file(config_file_path)
+        .map_err(|searched| Error::ConfigNotFound { searched })?;
+
+    let raw = read_raw_toml(&config_file_path).map_err(|source| Error::ConfigRead {
+        path: config_file_path.clone(),
+        source,
+    })?;
+
+    Ok((config_file_path, raw))
+}
+
+/// Read the config and init logging by it
+fn load_config(config_file_path: Option<PathBuf>) -> Result<Config, Error> {
+    let (config_file_path, raw) = read_config_file(config_file_path)?;
+    let config = Config::parse_raw_toml(raw).map_err(|source| Error::ConfigParse {
+        path: config_file_path,
+        source,
+    })?;
 
     tracing_subscriber::registry()
         .with(fmt::layer())
-        .with(EnvFilter::new(config.logging.directives))
+        .with(EnvFilter::new(&*config.logging.directives))
         .init();
 
-    info!("Init client");
-    let client = auth::init(&config.client).await;
+    Ok(config)
+}
 
-    info!("Authorize client");
-    auth::authorize(&client, &config.client).await;
+async fn run_offline(
+    config: &Config,
+    profile: Option<String>,
+    client_overrides: ClientOverrides,
+    command: OfflineCommand,
+) -> Result<(), Error> {
+    match command {
+        OfflineCommand::SessionEncrypt => {
+            let overrides = cli_overrides(client_overrides)?.or(Overrides::from_env()?);
+            let profile = config.profile(profile.as_deref(), overrides)?;
 
-    info!("Client connected and authorized successfully");
+            commands::encrypt_session(&profile.client)?;
+        }
+        OfflineCommand::Ignore(command) => commands::ignore(command, &config.ignore_file_path())?,
+        OfflineCommand::Replay {
+            analyze,
+            fixture_path,
+        } => commands::replay_analysis(&analyze, &fixture_path, &config.ignore_file_path()).await?,
+        OfflineCommand::Export {
+            analyze,
+            export_path,
+        } => commands::export_analysis(&analyze, &export_path, &config.ignore_file_path()).await?,
+    };
+
+    Ok(())
+}
+
+/// A failed profile doesn't stop the analysis of the rest. The chats of all profiles are shown
+/// after the analysis, as one list.
+async fn analyze_all_profiles(
+    config: &Config,
+    overrides: &Overrides,
+    analyze: &Analyze,
+) -> Result<(), Error> {
+    let mut found = vec![];
+    let mut failed = vec![];
+    for profile in config.profiles(overrides)? {
+        println!("Analyze profile `{name}`", name = profile.name);
+
+        let result = match connect(&profile.client, true).await {
+            Ok(client) => {
+                commands::analyze_profile(
+                    analyze,
+                    &client,
+                    profile.name,
+                    &config.ignore_file_path(),
+                )
+                .await
+            }
+            Err(err) => Err(err),
+        };
+        match result {
+            Ok(chats) => found.push(chats),
+            Err(err) => {
+                eprintln!("Error in profile `{name}`: {err}", name = profile.name);
+
+                failed.push(profile.name.into());
+            }
+        }
+    }
+
+    commands::show_all_profiles(analyze, &found)?;
+
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::ProfilesFailed {
+            names: failed.into(),
+        })
+    }
+}
+
+async fn run(
+    config_file_path: Option<PathBuf>,
+    profile: Option<String>,
+    client_overrides: ClientOverrides,
+    command: Commands,
+) -> Result<(), Error> {
+    match Command::from(command) {
+        // The config file doesn't exist yet
+        Command::ConfigInit => commands::init_config(new_config_file_path(config_file_path)),
+        Command::ConfigCheck => {
+            let (config_file_path, raw) = read_config_file(config_file_path)?;
+            let overrides = cli_overrides(client_overrides)?.or(Overrides::from_env()?);
+
+            commands::check_config(&raw, &overrides, config_file_path)
+        }
+        Command::Offline(command) => {
+            let config = load_config(config_file_path)?;
+
+            run_offline(&config, profile, client_overrides, command).await
+        }
+        Command::AnalyzeAllProfiles(analyze) => {
+            let config = load_config(config_file_path)?;
+            let overrides = cli_overrides(client_overrides)?.or(Overrides::from_env()?);
+
+            analyze_all_profiles(&config, &overrides, &analyze).await
+        }
+        Command::Connected(command) => {
+            let config = load_config(config_file_path)?;
+
+            // Precedence of client settings: CLI, environment variables and the config
+            let overrides = cli_overrides(client_overrides)?.or(Overrides::from_env()?);
+            let profile = config.profile(profile.as_deref(), overrides)?;
+
+            info!(profile = profile.name, "Use profile");
+
+            let client = connect(&profile.client, command.requires_authorization()).await?;
+
+            cli_run(&client, &config, &profile, command).await
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let Cli {
+        config_file_path,
+        profile,
+        client_overrides,
+        command,
+    } = cli_parse();
 
-    cli_run(&client, command).await;
+    match run(config_file_path, profile, client_overrides, command).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("Error: {err}");
+            ExitCode::FAILURE
+        }
+    }
 }